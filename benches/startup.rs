@@ -0,0 +1,127 @@
+//! Times the commands shell prompts and status bars run, against a generated
+//! store of 10,000 tasks. Run with `cargo bench`, which fails when a cached
+//! `count` or `focus` goes over the budget of a prompt.
+
+use std::{
+    env, fs,
+    path::Path,
+    process::{self, Command},
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+const TASKS: u32 = 10_000;
+const RUNS: usize = 25;
+const PROMPT_BUDGET: Duration = Duration::from_millis(5);
+
+fn main() {
+    let directory = env::temp_dir().join(format!("taskrs-bench-{}", process::id()));
+
+    fs::create_dir_all(&directory).expect("temporary directory created");
+    fs::write(directory.join("tasks.json"), generate_store().to_string()).expect("store written");
+
+    let mut over_budget = Vec::new();
+
+    // Name, arguments, whether the cache is kept, whether prompts run it.
+    for (name, args, cached, prompt) in [
+        ("--help", &["--help"][..], false, false),
+        ("count", &["count"], true, true),
+        ("count, uncached", &["count"], false, false),
+        ("focus", &["focus"], true, true),
+        ("list", &["list"], true, false),
+        ("list, uncached", &["list"], false, false),
+        ("list --sort due", &["list", "--sort", "due"], true, false),
+    ] {
+        let time = median_time(&directory, args, cached);
+
+        println!("{name:<20} {:>8.2} ms", time.as_secs_f64() * 1000.0);
+
+        if prompt && time > PROMPT_BUDGET {
+            over_budget.push(name);
+        }
+    }
+
+    let _ = fs::remove_dir_all(&directory);
+
+    if !over_budget.is_empty() {
+        eprintln!(
+            "Over the {} ms budget of a prompt: {}",
+            PROMPT_BUDGET.as_millis(),
+            over_budget.join(", ")
+        );
+        process::exit(1);
+    }
+}
+
+/// Pending and done tasks with the fields people use the most.
+fn generate_store() -> Value {
+    let tasks: Vec<Value> = (1..=TASKS)
+        .map(|id| {
+            let mut task = json!({
+                "id": id,
+                "uuid": format!("{id:08x}-0000-4000-8000-000000000000"),
+                "task": format!("Task number {id} with a few words of text"),
+                "tags": [format!("tag{}", id % 20)],
+                "created_at": "2024-01-01T09:00:00Z",
+            });
+
+            if id % 3 == 0 {
+                task["status"] = json!("done");
+                task["completed_at"] = json!("2024-01-02T09:00:00Z");
+            }
+
+            if id % 5 == 0 {
+                task["due"] = json!(format!("2030-{:02}-{:02}", id % 12 + 1, id % 28 + 1));
+            }
+
+            if id % 7 == 0 {
+                task["priority"] = json!("high");
+            }
+
+            if id == TASKS {
+                task["focused"] = json!(true);
+            }
+
+            task
+        })
+        .collect();
+
+    json!(tasks)
+}
+
+/// Median time of `taskrs <args>`, the cache being kept between runs when
+/// `cached` is set and removed before each run otherwise.
+fn median_time(directory: &Path, args: &[&str], cached: bool) -> Duration {
+    let cache = directory.join("tasks.cache");
+    let run = || {
+        let status = Command::new(env!("CARGO_BIN_EXE_taskrs"))
+            .arg("--path")
+            .arg(directory)
+            .arg("--no-nag")
+            .args(args)
+            .output()
+            .expect("taskrs runs")
+            .status;
+
+        assert!(status.success(), "taskrs {} failed", args.join(" "));
+    };
+
+    // Fills the cache, and the file system caches.
+    run();
+
+    let mut times: Vec<Duration> = (0..RUNS)
+        .map(|_| {
+            if !cached {
+                let _ = fs::remove_file(&cache);
+            }
+
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .collect();
+
+    times.sort_unstable();
+    times[RUNS / 2]
+}
@@ -0,0 +1,27 @@
+use std::{env, fs::File, io, path::PathBuf};
+
+use clap::CommandFactory;
+use clap_mangen::Man;
+
+/// The parts of the library used by the CLI definitions.
+#[allow(dead_code)]
+mod taskrs {
+    include!("src/values.rs");
+}
+
+#[allow(dead_code)]
+mod cli {
+    use super::taskrs;
+
+    include!("src/cli.rs");
+}
+
+fn main() -> io::Result<()> {
+    println!("cargo:rerun-if-changed=src/cli.rs");
+    println!("cargo:rerun-if-changed=src/values.rs");
+
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").ok_or(io::ErrorKind::NotFound)?);
+    let mut file = File::create(out_dir.join("taskrs.1"))?;
+
+    Man::new(cli::Cli::command()).render(&mut file)
+}
@@ -0,0 +1,730 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{builder::RangedU64ValueParser, Args, Parser, Subcommand, ValueEnum};
+use taskrs::{
+    parse_date, parse_estimate, parse_moment, DeleteMode, Period, Priority, Selector, SortKey,
+    Status, TaskRef,
+};
+
+#[derive(Parser)]
+#[command(about = "A simple command line to-do manager")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    #[arg(
+        short,
+        long,
+        env = "TASKRS_PATH",
+        help = "The path where to find and store the tasks.json file"
+    )]
+    pub path: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Report each change to the tasks on stderr in this format"
+    )]
+    pub events: Option<EventFormat>,
+
+    #[arg(
+        long,
+        help = "Skip the command if one with the same key already changed the tasks"
+    )]
+    pub idempotency_key: Option<String>,
+
+    #[arg(long, help = "Don't warn about overdue and soon due tasks")]
+    pub no_nag: bool,
+
+    /// Aborts in the middle of the next write, to check crash recovery.
+    #[arg(long, hide = true)]
+    pub simulate_crash: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    #[command(about = "Add a task")]
+    Add(AddArgs),
+
+    #[command(about = "List tasks")]
+    List(ListArgs),
+
+    #[command(about = "Find tasks by their text or notes")]
+    Search {
+        query: String,
+
+        #[arg(short, long, help = "Read the query as a regular expression")]
+        regex: bool,
+
+        #[arg(short, long, help = "Include done tasks")]
+        all: bool,
+    },
+
+    #[command(about = "List projects with their task counts")]
+    Projects,
+
+    #[command(about = "Pick a random pending task")]
+    Random {
+        #[arg(help = "Only pick among tasks containing this text")]
+        filter: Option<String>,
+    },
+
+    #[command(about = "Suggest the most urgent tasks that fit in the time available")]
+    Suggest(SuggestArgs),
+
+    #[cfg(feature = "tui")]
+    #[command(about = "Browse and edit tasks in a full-screen interface")]
+    Tui,
+
+    #[command(about = "Update a task")]
+    Update { id: TaskRef, task: String },
+
+    #[command(about = "Edit the text and notes of a task in $VISUAL or $EDITOR")]
+    Edit { id: TaskRef },
+
+    #[command(about = "Manage the notes pinned above the list")]
+    NoteList {
+        #[command(subcommand)]
+        command: NoteListCommands,
+    },
+
+    #[command(about = "Set the notes of a task")]
+    Note {
+        id: TaskRef,
+        notes: String,
+
+        #[arg(short, long, help = "Append to the existing notes")]
+        append: bool,
+    },
+
+    #[command(about = "Show a task with its notes")]
+    Show { id: TaskRef },
+
+    #[command(about = "Open the URL of a task in the default browser")]
+    Open { id: TaskRef },
+
+    #[command(about = "Mark tasks as done")]
+    Done {
+        #[arg(
+            required_unless_present = "text",
+            conflicts_with = "text",
+            value_delimiter = ',',
+            help = "Ids, UUID prefixes or ranges of ids of the tasks (e.g. 1-5,8)"
+        )]
+        ids: Vec<Selector>,
+
+        #[arg(
+            short = 'm',
+            long = "match",
+            value_name = "TEXT",
+            help = "Mark the only pending task containing this text"
+        )]
+        text: Option<String>,
+
+        #[arg(short, long, help = "Also mark all their subtasks")]
+        recursive: bool,
+    },
+
+    #[command(about = "Mark tasks as undone")]
+    Undone {
+        #[command(flatten)]
+        tasks: SelectArgs,
+
+        #[arg(short, long, help = "Also mark all their subtasks")]
+        recursive: bool,
+    },
+
+    #[command(about = "Set the status of a task")]
+    Status {
+        id: TaskRef,
+
+        #[arg(value_enum)]
+        status: Status,
+
+        #[arg(short, long, help = "Also set it on all its subtasks")]
+        recursive: bool,
+    },
+
+    #[command(about = "Reschedule or drop the overdue tasks one by one")]
+    Triage,
+
+    #[command(about = "Set the priority of a task")]
+    Priority {
+        id: TaskRef,
+
+        #[arg(value_enum, help = "Priority level, omit to clear it")]
+        level: Option<Priority>,
+    },
+
+    #[command(about = "Assign a task to someone")]
+    Assign {
+        id: TaskRef,
+
+        #[arg(help = "Name of the assignee, omit to unassign the task")]
+        name: Option<String>,
+    },
+
+    #[command(about = "Set the expected effort of a task")]
+    Estimate {
+        id: TaskRef,
+
+        #[arg(value_parser = parse_estimate, help = "Effort (e.g. 30m, 2h or 1h30m), omit to clear it")]
+        estimate: Option<u32>,
+    },
+
+    #[command(about = "Add or remove tags")]
+    Tag {
+        #[command(subcommand)]
+        command: TagCommands,
+    },
+
+    #[command(about = "Manage the checklist of a task")]
+    Check {
+        #[command(subcommand)]
+        command: CheckCommands,
+    },
+
+    #[command(about = "Attach custom key/value data to tasks")]
+    Meta {
+        #[command(subcommand)]
+        command: MetaCommands,
+    },
+
+    #[command(about = "Inspect the auto-tagging rules from config.toml")]
+    Rules {
+        #[command(subcommand)]
+        command: RulesCommands,
+    },
+
+    #[command(about = "Focus on a single task, or print the focused one")]
+    Focus {
+        #[arg(help = "Task to focus on, omit to print the focused task")]
+        id: Option<TaskRef>,
+    },
+
+    #[command(about = "Hide a task until a given date")]
+    Defer {
+        id: TaskRef,
+
+        #[arg(value_parser = parse_date, help = "Date when the task shows up again, omit to clear it")]
+        until: Option<NaiveDate>,
+    },
+
+    #[command(about = "Print the tasks whose reminder is due, or manage reminders")]
+    Remind {
+        #[command(subcommand)]
+        command: Option<RemindCommands>,
+    },
+
+    #[command(about = "Start tracking time on a task, putting it in progress")]
+    Start { id: TaskRef },
+
+    #[command(about = "Stop tracking time on the running task")]
+    Stop,
+
+    #[command(about = "Show the time tracked on a task")]
+    Times { id: TaskRef },
+
+    #[command(about = "Make a task depend on another one")]
+    Block {
+        id: TaskRef,
+
+        #[arg(long, help = "Id or UUID prefix of the task it depends on")]
+        on: TaskRef,
+    },
+
+    #[command(about = "Remove dependencies of a task")]
+    Unblock {
+        id: TaskRef,
+
+        #[arg(long, help = "Only remove the dependency on this task")]
+        on: Option<TaskRef>,
+    },
+
+    #[command(about = "Revert the last change to the tasks")]
+    Undo,
+
+    #[command(about = "Reapply the last change undone")]
+    Redo,
+
+    #[command(about = "Delete tasks")]
+    Delete {
+        #[command(flatten)]
+        tasks: SelectArgs,
+
+        #[arg(
+            short,
+            long,
+            value_enum,
+            help = "What to do with its subtasks and dependent tasks (prompts if omitted)"
+        )]
+        mode: Option<DeleteMode>,
+    },
+
+    #[command(about = "Move tasks to the archive file")]
+    Archive {
+        id: Option<TaskRef>,
+
+        #[arg(
+            long,
+            conflicts_with = "id",
+            required_unless_present = "id",
+            help = "Archive all done and cancelled tasks"
+        )]
+        done: bool,
+    },
+
+    #[command(about = "Move a task to a position of `list --sort manual`, shifting the others")]
+    Move {
+        id: TaskRef,
+
+        #[arg(
+            value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+            help = "New position, 1 being the top"
+        )]
+        position: usize,
+    },
+
+    #[command(about = "Swap tasks")]
+    Swap { id1: TaskRef, id2: TaskRef },
+
+    #[command(about = "Empty the task list")]
+    Reset {
+        #[arg(short, long, help = "Don't prompt for confirmation")]
+        force: bool,
+    },
+
+    #[command(about = "Convert the store to the format and compression set in config.toml")]
+    Migrate,
+
+    #[command(about = "Repair references to missing tasks")]
+    Doctor,
+
+    #[command(about = "Get information about your tasks")]
+    Infos {
+        #[arg(long, value_enum, default_value_t = Format::Text, help = "Output format")]
+        format: Format,
+
+        #[arg(
+            long,
+            help = "Also check the files: format, archive, undo history and cache"
+        )]
+        full: bool,
+    },
+
+    #[command(about = "Write reports on the tasks")]
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+
+    #[command(about = "Print the number of pending tasks")]
+    Count,
+
+    #[command(about = "Print the shell integration script")]
+    ShellInit { shell: Shell },
+
+    #[command(about = "Print the man page")]
+    Man,
+
+    #[command(
+        about = "Apply commands read from stdin in a single write",
+        long_about = "Apply commands read from stdin in a single write\n\n\
+                      Commands are given one per line (e.g. `add \"Buy milk\"`) or as a JSON \
+                      array of argument lists (e.g. `[[\"add\", \"Buy milk\"], [\"done\", \"3\"]]`). \
+                      Nothing is applied if any command is invalid."
+    )]
+    Batch,
+
+    #[command(external_subcommand)]
+    Plugin(Vec<String>),
+}
+
+#[derive(Args)]
+pub struct AddArgs {
+    #[arg(
+        required_unless_present = "url",
+        help = "Text of the task, defaults to the title of the --url page"
+    )]
+    pub task: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Points earned when the task is done"
+    )]
+    pub points: u32,
+
+    #[arg(long, value_parser = parse_date, help = "Due date (YYYY-MM-DD, today, tomorrow or a weekday)")]
+    pub due: Option<NaiveDate>,
+
+    #[arg(long = "wait", value_parser = parse_date, help = "Hide the task until this date")]
+    pub wait_until: Option<NaiveDate>,
+
+    #[arg(long, value_parser = parse_estimate, help = "Expected effort (e.g. 30m, 2h or 1h30m)")]
+    pub estimate: Option<u32>,
+
+    #[arg(long, value_enum, help = "Priority level")]
+    pub priority: Option<Priority>,
+
+    #[arg(short, long = "tag", help = "Tag to attach, can be repeated")]
+    pub tags: Vec<String>,
+
+    #[arg(long, help = "Id or UUID prefix of the parent task")]
+    pub parent: Option<TaskRef>,
+
+    #[arg(short = 'P', long, help = "Project the task belongs to")]
+    pub project: Option<String>,
+
+    #[arg(long, help = "Person the task is assigned to")]
+    pub assignee: Option<String>,
+
+    #[arg(long, help = "Where the task can be done (e.g. errands)")]
+    pub location: Option<String>,
+
+    #[arg(long, help = "Link to open with `taskrs open`")]
+    pub url: Option<String>,
+
+    #[arg(
+        long,
+        help = "Scratch task, removed after today and left out of statistics"
+    )]
+    pub ephemeral: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Read #tags, !priority and due dates (e.g. tomorrow) from the task text"
+    )]
+    pub quick: bool,
+}
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Args)]
+pub struct ListArgs {
+    #[arg(short, long, help = "Include done tasks")]
+    pub all: bool,
+
+    #[arg(long, help = "Show subtasks indented below their parent")]
+    pub tree: bool,
+
+    #[arg(long, help = "Show deferred tasks instead of the current ones")]
+    pub waiting: bool,
+
+    #[arg(long, help = "Show archived tasks instead of the current ones")]
+    pub archived: bool,
+
+    #[arg(long, help = "Only show tasks with a URL, as a reading list")]
+    pub links: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Only show tasks with this status, can be repeated"
+    )]
+    pub status: Vec<Status>,
+
+    #[arg(long, conflicts_with = "undone", help = "Only show done tasks")]
+    pub done: bool,
+
+    #[arg(long, help = "Only show tasks that are not done or cancelled")]
+    pub undone: bool,
+
+    #[arg(long, value_enum, help = "Only show tasks with this priority")]
+    pub priority: Option<Priority>,
+
+    #[arg(long, value_parser = parse_date, help = "Only show tasks due before this date")]
+    pub due_before: Option<NaiveDate>,
+
+    #[arg(long, value_parser = parse_date, help = "Only show tasks due after this date")]
+    pub due_after: Option<NaiveDate>,
+
+    #[arg(
+        short,
+        long = "tag",
+        help = "Only show tasks with this tag, can be repeated"
+    )]
+    pub tags: Vec<String>,
+
+    #[arg(
+        long,
+        value_parser = parse_meta_filter,
+        help = "Only show tasks with this metadata (key or key=value), can be repeated"
+    )]
+    pub meta: Vec<(String, Option<String>)>,
+
+    #[arg(short = 'P', long, help = "Only show tasks of this project")]
+    pub project: Option<String>,
+
+    #[arg(long, help = "Only show tasks assigned to this person")]
+    pub assignee: Option<String>,
+
+    #[arg(long, help = "Only show tasks that can be done at this location")]
+    pub location: Option<String>,
+
+    #[arg(
+        long,
+        value_parser = parse_estimate,
+        help = "Only show tasks estimated to fit in this time (e.g. 2h)"
+    )]
+    pub fits: Option<u32>,
+
+    #[arg(long, help = "Show creation and completion times")]
+    pub timestamps: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Order of the tasks [default: id, or set in config]"
+    )]
+    pub sort: Option<SortKey>,
+
+    #[arg(short, long, help = "Reverse the order of the tasks")]
+    pub reverse: bool,
+
+    #[arg(
+        long,
+        help = "Print a numbered plain list grouped by tag, for phones and paper"
+    )]
+    pub checklist: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Maximum table width (defaults to the terminal width)"
+    )]
+    pub width: Option<usize>,
+
+    #[arg(short, long, help = "Print a summary line after the table")]
+    pub summary: bool,
+}
+
+/// Tasks targeted by a command, by id or by text.
+#[derive(Args)]
+pub struct SelectArgs {
+    #[arg(
+        required_unless_present = "text",
+        value_delimiter = ',',
+        help = "Ids, UUID prefixes or ranges of ids of the tasks (e.g. 1-5,8)"
+    )]
+    pub ids: Vec<Selector>,
+
+    #[arg(
+        long = "match",
+        value_name = "TEXT",
+        help = "Also select the tasks containing this text"
+    )]
+    pub text: Option<String>,
+}
+
+impl SelectArgs {
+    pub fn into_selectors(self) -> Vec<Selector> {
+        let mut selectors = self.ids;
+        selectors.extend(self.text.map(Selector::Matching));
+
+        selectors
+    }
+}
+
+#[derive(Args)]
+pub struct SuggestArgs {
+    #[arg(
+        long,
+        value_parser = parse_estimate,
+        help = "Time available (e.g. 25m or 1h)"
+    )]
+    pub time: u32,
+
+    #[arg(
+        short,
+        long = "tag",
+        help = "Only suggest tasks with this tag, can be repeated"
+    )]
+    pub tags: Vec<String>,
+
+    #[arg(long, help = "Only suggest tasks that can be done at this location")]
+    pub location: Option<String>,
+
+    #[arg(short = 'n', long, default_value_t = 3, help = "Number of suggestions")]
+    pub count: usize,
+}
+
+fn parse_meta_filter(value: &str) -> Result<(String, Option<String>), String> {
+    let (key, value) = match value.split_once('=') {
+        Some((key, value)) => (key, Some(value.to_string())),
+        None => (value, None),
+    };
+
+    if key.is_empty() {
+        return Err("the metadata key cannot be empty".to_string());
+    }
+
+    Ok((key.to_string(), value))
+}
+
+#[derive(Subcommand)]
+pub enum TagCommands {
+    #[command(about = "Add tags to tasks")]
+    Add {
+        #[command(flatten)]
+        tasks: SelectArgs,
+
+        #[arg(
+            short,
+            long = "tag",
+            required = true,
+            help = "Tag to add, can be repeated"
+        )]
+        tags: Vec<String>,
+
+        #[arg(long, help = "Leave the subtasks without the tags")]
+        no_propagate: bool,
+    },
+
+    #[command(about = "Remove tags from tasks")]
+    Rm {
+        #[command(flatten)]
+        tasks: SelectArgs,
+
+        #[arg(
+            short,
+            long = "tag",
+            required = true,
+            help = "Tag to remove, can be repeated"
+        )]
+        tags: Vec<String>,
+
+        #[arg(long, help = "Leave the tags on the subtasks")]
+        no_propagate: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CheckCommands {
+    #[command(about = "Add an item to the checklist")]
+    Add { id: TaskRef, item: String },
+
+    #[command(about = "Check an item, by its number in `show`")]
+    Done { id: TaskRef, number: usize },
+
+    #[command(about = "Uncheck an item, by its number in `show`")]
+    Undone { id: TaskRef, number: usize },
+}
+
+#[derive(Subcommand)]
+pub enum RemindCommands {
+    #[command(about = "Add a reminder to a task", alias = "set")]
+    Add {
+        id: TaskRef,
+
+        #[arg(
+            value_parser = parse_moment,
+            required_unless_present = "before_due",
+            conflicts_with = "before_due",
+            help = "When to remind (e.g. 30m, 14:00, 9am, tomorrow or \"friday 9:30\")"
+        )]
+        when: Option<DateTime<Utc>>,
+
+        #[arg(
+            long,
+            value_parser = parse_estimate,
+            value_name = "DELAY",
+            help = "Remind this long before the task is due at 9:00 (e.g. 1h)"
+        )]
+        before_due: Option<u32>,
+    },
+
+    #[command(about = "Remove the reminders of a task")]
+    Clear { id: TaskRef },
+}
+
+#[derive(Subcommand)]
+pub enum ReportCommands {
+    #[command(
+        about = "Summarize the tasks completed, added and overdue over a period",
+        long_about = "Summarize the tasks completed, added and overdue over a period\n\n\
+                      With --output, the report goes to a dated file, e.g. to send from a \
+                      weekly cron job."
+    )]
+    Summary {
+        #[arg(long, value_enum, default_value_t = Period::Week, help = "Period covered, ending today")]
+        period: Period,
+
+        #[arg(long, value_enum, default_value_t = ReportFormat::Markdown, help = "Output format")]
+        format: ReportFormat,
+
+        #[arg(
+            short,
+            long,
+            value_name = "DIR",
+            help = "Write the report to a file in this directory instead of printing it"
+        )]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum NoteListCommands {
+    #[command(about = "Edit the pinned notes in $VISUAL or $EDITOR")]
+    Edit,
+
+    #[command(about = "Print the pinned notes")]
+    Show,
+
+    #[command(about = "Remove the pinned notes")]
+    Clear,
+}
+
+#[derive(Subcommand)]
+pub enum MetaCommands {
+    #[command(about = "Set a metadata value")]
+    Set {
+        id: TaskRef,
+        key: String,
+        value: String,
+    },
+
+    #[command(about = "Print a metadata value, or all of them")]
+    Get { id: TaskRef, key: Option<String> },
+
+    #[command(about = "Remove a metadata value")]
+    Del { id: TaskRef, key: String },
+}
+
+#[derive(Subcommand)]
+pub enum RulesCommands {
+    #[command(about = "Show which rules would apply to a task")]
+    Test { task: String },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum EventFormat {
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+pub struct BatchCommand {
+    #[command(subcommand)]
+    pub command: Commands,
+}
@@ -0,0 +1,161 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{fmt, fs, io, path::Path};
+
+use crate::{Priority, SortKey, Status};
+
+/// User settings read from the `config.toml` stored next to `tasks.json`.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Tidy up the text of added tasks (whitespace, capitalization, trailing punctuation).
+    pub normalize: bool,
+    /// Suggest existing tags mentioned in the text of added tasks.
+    pub suggest_tags: bool,
+    /// Longest text `add` accepts without offering to store it as notes, 200
+    /// characters by default.
+    pub max_task_length: Option<usize>,
+    /// Most lines `add` accepts without offering to store the text as notes,
+    /// 1 by default.
+    pub max_task_lines: Option<usize>,
+    /// How the store is written. Both formats are always readable, `taskrs
+    /// migrate` converts existing files after a change.
+    pub format: StoreFormat,
+    /// Compress the store with zstd, which needs the `compress` feature.
+    /// Compressed and plain files are then both readable.
+    pub compress: bool,
+    /// Default order of `list`, overridden by `--sort`.
+    pub sort: SortKey,
+    /// Print the summary footer of `list` even without `--summary`.
+    pub summary: bool,
+    /// Show the points of tasks and the score in `show` and `infos`, on by
+    /// default.
+    pub points: bool,
+    /// Warn before each command about overdue tasks and tasks due within
+    /// this many days, unless `--no-nag` is given.
+    pub nag: Option<u32>,
+    pub rules: Vec<Rule>,
+    /// Work-in-progress limits checked by `status` and `start`.
+    pub wip_limits: Vec<WipLimit>,
+    /// Changes made to tasks left open for too long, before each command.
+    pub aging: Vec<AgingRule>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            normalize: false,
+            suggest_tags: false,
+            max_task_length: None,
+            max_task_lines: None,
+            format: StoreFormat::default(),
+            compress: false,
+            sort: SortKey::default(),
+            summary: false,
+            points: true,
+            nag: None,
+            rules: Vec::new(),
+            wip_limits: Vec::new(),
+            aging: Vec::new(),
+        }
+    }
+}
+
+/// On-disk format of the task store.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreFormat {
+    #[default]
+    Json,
+    /// Versioned `MessagePack`, smaller and faster to parse for huge stores.
+    Binary,
+}
+
+impl fmt::Display for StoreFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Json => "json",
+            Self::Binary => "binary",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl Config {
+    /// Reads the configuration at `path`, falling back to the defaults when
+    /// the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the file cannot be read or is not a valid configuration.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(error.into()),
+        };
+
+        toml::from_str(&content)
+            .with_context(|| format!("Invalid configuration in {}", path.as_ref().display()))
+    }
+}
+
+/// Attributes added to new tasks whose text matches a pattern.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    #[serde(rename = "match", deserialize_with = "deserialize_regex")]
+    pub pattern: Regex,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub priority: Option<Priority>,
+    pub project: Option<String>,
+}
+
+impl Rule {
+    pub fn matches(&self, text: &str) -> bool {
+        self.pattern.is_match(text)
+    }
+}
+
+/// Most tasks allowed in a status, in progress by default, optionally only
+/// counting the ones with a tag.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct WipLimit {
+    #[serde(default = "in_progress")]
+    pub status: Status,
+    pub tag: Option<String>,
+    pub max: usize,
+    /// Refuse the changes going over the limit instead of warning.
+    #[serde(default)]
+    pub block: bool,
+}
+
+/// Escalates the tasks still open `days` after they were added, optionally
+/// only the ones with a tag. Each rule applies once to a task, which keeps
+/// the names of the rules applied to it.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AgingRule {
+    pub name: String,
+    pub days: u32,
+    pub tag: Option<String>,
+    /// Raise the priority one level, up to high.
+    #[serde(default)]
+    pub raise_priority: bool,
+    pub add_tag: Option<String>,
+}
+
+const fn in_progress() -> Status {
+    Status::InProgress
+}
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+
+    Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
@@ -1,196 +1,4371 @@
-use anyhow::Result;
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Days, Local, Months, NaiveDate, TimeDelta, Utc};
+use regex::Regex;
+use serde::{
+    de::{SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::json;
 use std::{
-    fs::{File, OpenOptions},
-    io::{self, BufReader, BufWriter},
+    cell::{Cell, RefCell},
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, BTreeSet},
+    env,
+    fmt::{self, Write as _},
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write as _},
     path::{Path, PathBuf},
+    process,
+    time::UNIX_EPOCH,
+};
+use tabled::{
+    builder::Builder,
+    settings::{object::Columns, peaker::PriorityMax, Disable, Style, Width},
+    Table, Tabled,
+};
+use uuid::Uuid;
+
+pub use config::{Config, StoreFormat};
+pub use quick::{parse_quick, Draft};
+use values::DEFAULT_TIME;
+pub use values::{
+    parse_date, parse_estimate, parse_moment, DeleteMode, Period, Priority, Selector, SortKey,
+    Status, TaskRef,
 };
-use tabled::{settings::Style, Table, Tabled};
 
-#[derive(Serialize, Deserialize, Debug, Tabled)]
+mod config;
+mod quick;
+#[cfg(feature = "tui")]
+mod tui;
+mod values;
+
+const POINTS_PER_LEVEL: u32 = 100;
+const MAX_TASK_LENGTH: usize = 200;
+const MAX_TASK_LINES: usize = 1;
+const SHORT_TITLE_LENGTH: usize = 50;
+/// Starts stores written in the binary format, followed by a version byte.
+const BINARY_MAGIC: &[u8] = b"TASKRS";
+const BINARY_VERSION: u8 = 1;
+#[cfg(feature = "fetch")]
+const MAX_PAGE_SIZE: u64 = 1024 * 1024;
+/// Days before its due date from which a task becomes more urgent.
+const URGENT_DAYS: i64 = 14;
+/// Operations kept in the undo journal.
+const MAX_JOURNAL_ENTRIES: usize = 50;
+const IDEMPOTENCY_KEY_DAYS: i64 = 30;
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const COMPRESS_UNSUPPORTED: &str =
+    "Compressed stores need taskrs built with the `compress` feature";
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Serialize, Deserialize, Debug, Clone, Tabled)]
 struct TaskItem {
     #[tabled(order = 0, rename = "")]
     id: u32,
 
-    #[tabled(order = 2, rename = "")]
+    /// Stays the same when the id changes. Stores written before UUIDs
+    /// existed get one assigned on first load.
+    #[serde(default)]
+    #[tabled(skip)]
+    uuid: Uuid,
+
+    #[tabled(order = 3, rename = "")]
     task: String,
 
-    #[tabled(display_with = "as_checkbox", order = 1, rename = "")]
-    done: bool,
+    #[serde(default)]
+    #[tabled(display_with = "display_status", order = 1, rename = "")]
+    status: Status,
+
+    /// Only read from stores written before statuses existed.
+    #[serde(default, rename = "done", skip_serializing)]
+    #[tabled(skip)]
+    legacy_done: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display_with = "display_priority", order = 2, rename = "")]
+    priority: Option<Priority>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[tabled(skip)]
+    focused: bool,
+
+    /// Scratch task, pruned the day after it was added and kept out of the
+    /// statistics and the archive.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[tabled(skip)]
+    ephemeral: bool,
+
+    #[serde(default, skip_serializing_if = "is_zero")]
+    #[tabled(skip)]
+    points: u32,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display_with("display_due", self), order = 10, rename = "")]
+    due: Option<NaiveDate>,
+
+    /// Expected effort, in minutes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display_with = "display_estimate", order = 11, rename = "")]
+    estimate: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[tabled(display_with = "display_tags", order = 8, rename = "")]
+    tags: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display_with = "display_notes", order = 5, rename = "")]
+    notes: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(skip)]
+    parent: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(skip)]
+    project: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display_with = "display_assignee", order = 9, rename = "")]
+    assignee: Option<String>,
+
+    /// Where the task can be done (e.g. errands, office), for people who
+    /// organize by place rather than project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(skip)]
+    location: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[tabled(skip)]
+    depends_on: Vec<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display_with = "display_url", order = 6, rename = "")]
+    url: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(skip)]
+    wait_until: Option<NaiveDate>,
+
+    /// When to remind about the task, earliest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[tabled(skip)]
+    reminders: Vec<DateTime<Utc>>,
+
+    /// Only read from stores written when tasks had a single reminder.
+    #[serde(default, rename = "remind_at", skip_serializing)]
+    #[tabled(skip)]
+    legacy_remind_at: Option<DateTime<Utc>>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[tabled(skip)]
+    time_entries: Vec<TimeEntry>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[tabled(display_with = "display_checklist", order = 7, rename = "")]
+    checklist: Vec<ChecklistItem>,
+
+    /// Names of the aging rules of the configuration already applied.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[tabled(skip)]
+    escalations: Vec<String>,
+
+    /// Free-form data set by scripts and integrations.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[tabled(skip)]
+    meta: BTreeMap<String, String>,
+
+    /// Whether a dependency is still pending, computed before rendering.
+    #[serde(skip)]
+    #[tabled(display_with = "display_blocked", order = 4, rename = "")]
+    blocked: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display_with = "display_timestamp", order = 12, rename = "")]
+    created_at: Option<DateTime<Utc>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display_with = "display_timestamp", order = 13, rename = "")]
+    completed_at: Option<DateTime<Utc>>,
 }
 
 impl TaskItem {
-    const fn new(id: u32, task: String) -> Self {
-        Self {
+    fn new(id: u32, task: String, options: &TaskOptions) -> Self {
+        let mut item = Self {
             id,
+            uuid: Uuid::new_v4(),
             task,
-            done: false,
+            status: Status::Todo,
+            legacy_done: false,
+            priority: options.priority,
+            focused: false,
+            ephemeral: options.ephemeral,
+            points: options.points,
+            due: options.due,
+            estimate: options.estimate,
+            tags: Vec::new(),
+            notes: None,
+            parent: None,
+            project: options.project.clone(),
+            assignee: options.assignee.clone(),
+            location: options.location.clone(),
+            depends_on: Vec::new(),
+            url: options.url.clone(),
+            wait_until: options.wait_until,
+            reminders: Vec::new(),
+            legacy_remind_at: None,
+            time_entries: Vec::new(),
+            checklist: Vec::new(),
+            escalations: Vec::new(),
+            meta: BTreeMap::new(),
+            blocked: false,
+            created_at: Some(Utc::now()),
+            completed_at: None,
+        };
+
+        item.add_tags(&options.tags);
+        item
+    }
+
+    fn has_tag(&self, tag: &str) -> bool {
+        let tag = normalize_tag(tag);
+
+        self.tags.contains(&tag)
+    }
+
+    fn add_tags(&mut self, tags: &[String]) {
+        for tag in tags {
+            if !tag.trim().is_empty() && !self.has_tag(tag) {
+                self.tags.push(normalize_tag(tag));
+            }
+        }
+    }
+
+    fn remove_tags(&mut self, tags: &[String]) {
+        let tags: Vec<String> = tags.iter().map(|tag| normalize_tag(tag)).collect();
+
+        self.tags.retain(|tag| !tags.contains(tag));
+    }
+
+    fn is_running(&self) -> bool {
+        self.time_entries.iter().any(|entry| entry.end.is_none())
+    }
+
+    fn stop_timer(&mut self, now: DateTime<Utc>) {
+        for entry in self
+            .time_entries
+            .iter_mut()
+            .filter(|entry| entry.end.is_none())
+        {
+            entry.end = Some(now);
+        }
+    }
+
+    fn tracked_time(&self, now: DateTime<Utc>) -> TimeDelta {
+        self.time_entries
+            .iter()
+            .map(|entry| entry.end.unwrap_or(now) - entry.start)
+            .sum()
+    }
+
+    fn is_waiting(&self, today: NaiveDate) -> bool {
+        self.wait_until.is_some_and(|date| date > today)
+    }
+
+    fn is_expired(&self, today: NaiveDate) -> bool {
+        self.ephemeral
+            && self
+                .created_at
+                .is_none_or(|created_at| created_at.with_timezone(&Local).date_naive() < today)
+    }
+
+    fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.is_open() && self.due.is_some_and(|due| due < today)
+    }
+
+    fn is_due_today(&self, today: NaiveDate) -> bool {
+        self.is_open() && self.due == Some(today)
+    }
+
+    /// How pressing the task is, from its due date, priority and status.
+    fn urgency(&self, today: NaiveDate) -> i64 {
+        let due = self.due.map_or(0, |due| {
+            (URGENT_DAYS - (due - today).num_days()).clamp(0, URGENT_DAYS)
+        });
+        let priority = match self.priority {
+            Some(Priority::High) => 6,
+            Some(Priority::Medium) => 3,
+            Some(Priority::Low) => 1,
+            None => 0,
+        };
+        let started = if self.status == Status::InProgress {
+            2
+        } else {
+            0
+        };
+
+        due + priority + started
+    }
+
+    /// Converts fields of older stores to their current form.
+    fn upgrade(&mut self) {
+        if self.legacy_done {
+            self.status = Status::Done;
+            self.legacy_done = false;
+        }
+
+        if let Some(remind_at) = self.legacy_remind_at.take() {
+            self.add_reminder(remind_at);
+        }
+    }
+
+    fn add_reminder(&mut self, at: DateTime<Utc>) {
+        if !self.reminders.contains(&at) {
+            self.reminders.push(at);
+            self.reminders.sort_unstable();
+        }
+    }
+
+    const fn is_open(&self) -> bool {
+        !self.status.is_closed()
+    }
+}
+
+/// A step of a task, too small to be a subtask of its own.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ChecklistItem {
+    text: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    done: bool,
+}
+
+/// Records which operation used an idempotency key.
+#[derive(Serialize, Deserialize)]
+struct AppliedKey {
+    operation: Uuid,
+    at: DateTime<Utc>,
+}
+
+/// Operations that can be undone, and undone ones that can be redone, the
+/// most recent last.
+#[derive(Serialize, Deserialize, Default)]
+struct Journal {
+    #[serde(default)]
+    undo: Vec<JournalEntry>,
+    #[serde(default)]
+    redo: Vec<JournalEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JournalEntry {
+    operation: Uuid,
+    at: DateTime<Utc>,
+    changes: Vec<Change>,
+    /// Removed tasks that went to the archive, by UUID.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    archived: Vec<Uuid>,
+    /// Set when the operation changed the order of the tasks, which the
+    /// changes don't capture.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    order: Option<Reorder>,
+}
+
+/// The order of the tasks, by UUID, before and after an operation.
+#[derive(Serialize, Deserialize)]
+struct Reorder {
+    before: Vec<Uuid>,
+    after: Vec<Uuid>,
+}
+
+/// One task before and after an operation, missing when it was added or
+/// removed.
+#[derive(Serialize, Deserialize)]
+struct Change {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    before: Option<TaskItem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    after: Option<TaskItem>,
+}
+
+/// Rendered outputs of read-only commands for one state of the store.
+#[derive(Serialize, Deserialize)]
+struct OutputCache {
+    stamp: String,
+    entries: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct TimeEntry {
+    start: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end: Option<DateTime<Utc>>,
+}
+
+impl Status {
+    const fn is_closed(self) -> bool {
+        matches!(self, Self::Done | Self::Cancelled)
+    }
+}
+
+impl Period {
+    /// First day of the period ending on `today`.
+    fn start(self, today: NaiveDate) -> NaiveDate {
+        let start = match self {
+            Self::Week => today.checked_sub_days(Days::new(6)),
+            Self::Month => today
+                .checked_sub_months(Months::new(1))
+                .and_then(|date| date.succ_opt()),
+        };
+
+        start.unwrap_or(NaiveDate::MIN)
+    }
+}
+
+impl SortKey {
+    fn compare(self, a: &TaskItem, b: &TaskItem) -> Ordering {
+        match self {
+            Self::Id => a.id.cmp(&b.id),
+            Self::Text => a.task.to_lowercase().cmp(&b.task.to_lowercase()),
+            Self::Due => a
+                .due
+                .is_none()
+                .cmp(&b.due.is_none())
+                .then(a.due.cmp(&b.due)),
+            Self::Priority => b.priority.cmp(&a.priority),
+            Self::Created => a.created_at.cmp(&b.created_at),
+            // Tasks are stored in manual order and sorts are stable.
+            Self::Manual => Ordering::Equal,
+        }
+    }
+}
+
+impl TaskRef {
+    fn matches(&self, task: &TaskItem) -> bool {
+        match self {
+            Self::Id(id) => task.id == *id,
+            Self::Uuid(prefix) => task
+                .uuid
+                .simple()
+                .to_string()
+                .starts_with(&prefix.replace('-', "")),
         }
     }
 }
 
+/// Optional attributes of a task being added.
+#[derive(Debug, Default, Clone)]
+pub struct TaskOptions {
+    pub points: u32,
+    pub due: Option<NaiveDate>,
+    /// Expected effort, in minutes.
+    pub estimate: Option<u32>,
+    pub priority: Option<Priority>,
+    pub tags: Vec<String>,
+    pub parent: Option<TaskRef>,
+    pub project: Option<String>,
+    pub assignee: Option<String>,
+    pub location: Option<String>,
+    pub wait_until: Option<NaiveDate>,
+    pub url: Option<String>,
+    pub ephemeral: bool,
+}
+
+/// Fetches the title of the web page at `url`, to name tasks added with only
+/// a link. Always `None` unless built with the `fetch` feature.
+#[cfg(feature = "fetch")]
+#[must_use]
+pub fn page_title(url: &str) -> Option<String> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(std::time::Duration::from_secs(5)))
+        .build()
+        .into();
+    let page = agent
+        .get(url)
+        .call()
+        .ok()?
+        .body_mut()
+        .with_config()
+        .limit(MAX_PAGE_SIZE)
+        .read_to_string()
+        .ok()?;
+    let title = Regex::new(r"(?is)<title[^>]*>(.*?)</title>")
+        .ok()?
+        .captures(&page)?
+        .get(1)?
+        .as_str()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+
+    Some(title).filter(|title| !title.is_empty())
+}
+
+#[cfg(not(feature = "fetch"))]
+#[must_use]
+pub const fn page_title(_url: &str) -> Option<String> {
+    None
+}
+
+/// Which tasks `list` shows and how.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Default, Clone)]
+pub struct ListOptions {
+    pub all: bool,
+    pub tree: bool,
+    pub waiting: bool,
+    pub archived: bool,
+    /// Only tasks with a URL, as a reading list.
+    pub links: bool,
+    pub statuses: Vec<Status>,
+    pub tags: Vec<String>,
+    /// Metadata keys that must be set, with the value they must have if any.
+    pub meta: Vec<(String, Option<String>)>,
+    pub project: Option<String>,
+    pub assignee: Option<String>,
+    pub location: Option<String>,
+    pub priority: Option<Priority>,
+    pub due_before: Option<NaiveDate>,
+    pub due_after: Option<NaiveDate>,
+    /// Only tasks estimated to take at most this many minutes.
+    pub fits: Option<u32>,
+    pub timestamps: bool,
+    /// Defaults to the `sort` of the configuration.
+    pub sort: Option<SortKey>,
+    pub reverse: bool,
+    /// A numbered plain list grouped by tag instead of tables.
+    pub checklist: bool,
+    pub width: Option<usize>,
+    pub summary: bool,
+}
+
+impl ListOptions {
+    fn matches(&self, task: &TaskItem, today: NaiveDate) -> bool {
+        (if self.statuses.is_empty() {
+            self.all || self.archived || task.is_open()
+        } else {
+            self.statuses.contains(&task.status)
+        }) && task.is_waiting(today) == self.waiting
+            && (!self.links || task.url.is_some())
+            && self.tags.iter().all(|tag| task.has_tag(tag))
+            && self.meta.iter().all(|(key, value)| {
+                task.meta
+                    .get(key)
+                    .is_some_and(|current| value.as_ref().is_none_or(|value| current == value))
+            })
+            && self
+                .project
+                .as_ref()
+                .is_none_or(|project| task.project.as_ref() == Some(project))
+            && self
+                .assignee
+                .as_ref()
+                .is_none_or(|assignee| task.assignee.as_ref() == Some(assignee))
+            && self
+                .location
+                .as_ref()
+                .is_none_or(|location| task.location.as_ref() == Some(location))
+            && self
+                .priority
+                .is_none_or(|priority| task.priority == Some(priority))
+            && self
+                .due_before
+                .is_none_or(|date| task.due.is_some_and(|due| due < date))
+            && self
+                .due_after
+                .is_none_or(|date| task.due.is_some_and(|due| due > date))
+            && self
+                .fits
+                .is_none_or(|fits| task.estimate.is_some_and(|estimate| estimate <= fits))
+    }
+}
+
 pub struct TaskStore {
     path: PathBuf,
+    config: Config,
+    pending: RefCell<Option<Vec<TaskItem>>>,
+    simulate_crash: bool,
+    events: bool,
+    /// Identifies this invocation in events and idempotency records.
+    operation: Uuid,
+    writes: Cell<usize>,
+    /// Tasks moved to the archive since the last entry of the undo journal.
+    archived: RefCell<Vec<Uuid>>,
+    /// Set when the store exists but could not be read, to never write over
+    /// it.
+    unreadable: Cell<bool>,
 }
 
 impl TaskStore {
     #[must_use]
-    pub const fn new(path: PathBuf) -> Self {
-        Self { path }
+    pub fn new(path: PathBuf, config: Config) -> Self {
+        Self {
+            path,
+            config,
+            pending: RefCell::new(None),
+            simulate_crash: false,
+            events: false,
+            operation: Uuid::new_v4(),
+            writes: Cell::new(0),
+            archived: RefCell::new(Vec::new()),
+            unreadable: Cell::new(false),
+        }
     }
 
-    pub fn add_task(&self, task: impl Into<String>) {
-        let mut tasks = read_tasks(&self.path).unwrap_or_default();
-        let max_id = tasks.iter().map(|task| task.id).max().unwrap_or(0);
-        let new_task = TaskItem::new(max_id + 1, task.into());
+    /// Reports every change to the tasks as a JSON object on stderr.
+    #[must_use]
+    pub const fn with_events(mut self) -> Self {
+        self.events = true;
+        self
+    }
 
-        tasks.push(new_task);
+    /// Makes the process abort in the middle of its next write, for testing
+    /// that an interrupted write leaves the store intact.
+    #[must_use]
+    pub const fn with_simulated_crash(mut self) -> Self {
+        self.simulate_crash = true;
+        self
+    }
 
-        if write_tasks(&self.path, &tasks).is_err() {
-            eprintln!("Could not write to {}", &self.path.display());
-        }
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
     }
 
-    pub fn list_tasks(&self, all: bool) {
-        let tasks = read_tasks(&self.path).unwrap_or_default();
-        let mut tasks: Vec<&TaskItem> = tasks.iter().filter(|task| !task.done || all).collect();
-        tasks.sort_by_key(|task| task.id);
+    /// Runs several operations against an in-memory copy of the tasks and
+    /// writes the result to disk once they all completed, reading and
+    /// writing the store at most once.
+    pub fn transaction(&self, operations: impl FnOnce(&Self)) {
+        // Nested transactions are part of the outer one, e.g. in a batch.
+        if self.pending.borrow().is_some() {
+            operations(self);
+            return;
+        }
 
-        let mut table = Table::new(tasks);
-        table.with(Style::blank());
+        let previous = self.read();
 
-        println!("{table}");
+        self.pending.replace(Some(previous.clone()));
+        operations(self);
+
+        if let Some(tasks) = self.pending.take() {
+            self.commit(Some(&previous), &tasks, true);
+        }
     }
 
-    pub fn update_task(&self, id: u32, task: impl Into<String>) {
-        let mut tasks = read_tasks(&self.path).unwrap_or_default();
-        let Some(current) = tasks.iter_mut().find(|task| task.id == id) else {
-            eprintln!("Task not found");
+    /// Runs `operation` unless an earlier invocation with the same `key`
+    /// already changed the tasks, so that retried scripts don't apply their
+    /// changes twice. Keys are forgotten after a while.
+    pub fn idempotent(&self, key: &str, operation: impl FnOnce(&Self)) {
+        let path = self.path.with_extension("keys.json");
+        let mut keys: BTreeMap<String, AppliedKey> = fs::read(&path)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+
+        if let Some(applied) = keys.get(key) {
+            eprintln!(
+                "Already applied by operation {} on {}",
+                applied.operation,
+                display_timestamp(&Some(applied.at))
+            );
             return;
-        };
+        }
 
-        current.task = task.into();
+        let writes = self.writes.get();
+        operation(self);
+
+        if self.writes.get() == writes || self.unreadable.get() {
+            return;
+        }
 
-        if write_tasks(&self.path, &tasks).is_err() {
-            eprintln!("Could not write to {}", &self.path.display());
+        let now = Utc::now();
+        keys.retain(|_, applied| now - applied.at < TimeDelta::days(IDEMPOTENCY_KEY_DAYS));
+        keys.insert(
+            key.to_string(),
+            AppliedKey {
+                operation: self.operation,
+                at: now,
+            },
+        );
+
+        match serde_json::to_vec(&keys) {
+            Ok(content) if fs::write(&path, &content).is_ok() => {}
+            _ => eprintln!("Could not write to {}", path.display()),
         }
     }
 
-    pub fn mark_task(&self, id: u32, done: bool) {
-        let mut tasks = read_tasks(&self.path).unwrap_or_default();
-        let Some(current) = tasks.iter_mut().find(|task| task.id == id) else {
-            eprintln!("Task not found");
-            return;
-        };
+    fn load(&self) -> Vec<TaskItem> {
+        let mut tasks = self.pending.borrow().clone().unwrap_or_else(|| self.read());
+        let count = tasks.len();
+        let mut changed = false;
+
+        for task in tasks.iter_mut().filter(|task| task.uuid.is_nil()) {
+            task.uuid = Uuid::new_v4();
+            changed = true;
+        }
 
-        current.done = done;
+        let today = today();
+        tasks.retain(|task| !task.is_expired(today));
 
-        if write_tasks(&self.path, &tasks).is_err() {
-            eprintln!("Could not write to {}", &self.path.display());
+        // Housekeeping, not worth an entry in the undo journal.
+        if changed || tasks.len() < count {
+            self.persist(&tasks, false);
         }
+
+        tasks
     }
 
-    pub fn delete_task(&self, id: u32) {
-        let mut tasks = read_tasks(&self.path).unwrap_or_default();
-        let Some(index) = tasks.iter().position(|task| task.id == id) else {
-            eprintln!("Task not found");
-            return;
+    /// Returns the output cached under `key` for the current state of the
+    /// store, rendering and caching it first if needed. Lets status bars call
+    /// `list` or `count` every few seconds without re-reading the tasks.
+    fn cached(&self, key: String, render: impl FnOnce() -> String) -> String {
+        let Some(stamp) = self.stamp() else {
+            return render();
         };
+        let cache_path = self.path.with_extension("cache");
+        let mut cache = fs::read_to_string(&cache_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<OutputCache>(&content).ok())
+            .filter(|cache| cache.stamp == stamp)
+            .unwrap_or_else(|| OutputCache {
+                stamp,
+                entries: BTreeMap::new(),
+            });
+
+        if let Some(output) = cache.entries.get(&key) {
+            return output.clone();
+        }
+
+        let output = render();
+
+        // What was rendered from a store that could not be read would hide
+        // the error on the next calls.
+        if self.unreadable.get() {
+            return output;
+        }
+
+        cache.entries.insert(key, output.clone());
+
+        // The cache only saves work, failing to write it is not an error.
+        if let Ok(content) = serde_json::to_string(&cache) {
+            let _ = fs::write(&cache_path, content);
+        }
+
+        output
+    }
+
+    /// Identifies the current state of the store, or `None` when it cannot
+    /// be cached (no file yet, or uncommitted changes of a transaction).
+    fn stamp(&self) -> Option<String> {
+        if self.pending.borrow().is_some() {
+            return None;
+        }
+
+        let metadata = fs::metadata(&self.path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+        // Overdue and waiting tasks depend on the date, not only on the file.
+        Some(format!(
+            "{}:{}:{}",
+            modified.as_nanos(),
+            metadata.len(),
+            today()
+        ))
+    }
+
+    /// Writes `tasks` to a temporary file renamed over `path` once complete
+    /// and flushed to disk, so neither an interrupted write nor a power loss
+    /// leaves a truncated file behind. Each write gets its own temporary
+    /// file, for processes writing at the same time not to mix theirs.
+    fn write(&self, path: &Path, tasks: &[TaskItem]) -> Result<()> {
+        let content = encode_tasks(tasks, &self.config)?;
+        let temporary = temporary_path(path);
+
+        let written = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temporary)
+            .and_then(|mut file| {
+                file.write_all(&content)?;
+                file.sync_all()
+            });
+
+        if self.simulate_crash {
+            process::abort();
+        }
+
+        if let Err(error) = written.and_then(|()| fs::rename(&temporary, path)) {
+            let _ = fs::remove_file(&temporary);
+            return Err(error.into());
+        }
+
+        // The tasks are in place either way, this only makes the rename
+        // itself survive a power loss.
+        let _ = sync_directory(path);
+
+        Ok(())
+    }
+
+    /// Visits every task like `load` would return them, but streams them from
+    /// disk one at a time for commands that only aggregate. Returns `false`
+    /// when the store could not be read to the end, after reporting it, as
+    /// `f` then only saw part of the tasks.
+    fn scan(&self, mut f: impl FnMut(&TaskItem)) -> bool {
+        let today = today();
+
+        if let Some(tasks) = self.pending.borrow().as_ref() {
+            tasks
+                .iter()
+                .filter(|task| !task.is_expired(today))
+                .for_each(f);
+            return true;
+        }
+
+        let scanned = scan_tasks(&self.path, |task| {
+            if !task.is_expired(today) {
+                f(&task);
+            }
+        });
+
+        match scanned {
+            Ok(()) => true,
+            Err(error) if is_missing(&error) => true,
+            Err(error) => {
+                self.read_failed(&error);
+                false
+            }
+        }
+    }
+
+    /// Visits every archived task, for statistics covering the whole history.
+    /// Returns `false` like `scan` when the archive could not be read.
+    fn scan_archive(&self, mut f: impl FnMut(&TaskItem)) -> bool {
+        let path = self.archive_path();
+
+        if path.exists() && scan_tasks(&path, |task| f(&task)).is_err() {
+            eprintln!("Could not read {}", path.display());
+            return false;
+        }
+
+        true
+    }
+
+    /// Reads the tasks on disk, a missing store having none. Other errors are
+    /// reported and keep the store from being written over.
+    fn read(&self) -> Vec<TaskItem> {
+        read_tasks(&self.path).unwrap_or_else(|error| {
+            self.read_failed(&error);
+            Vec::new()
+        })
+    }
+
+    fn read_failed(&self, error: &anyhow::Error) {
+        if !is_missing(error) && !self.unreadable.replace(true) {
+            eprintln!("Could not read {}: {error:#}", self.path.display());
+        }
+    }
 
-        tasks.remove(index);
+    fn save(&self, tasks: &[TaskItem]) {
+        self.persist(tasks, true);
+    }
 
-        if write_tasks(&self.path, &tasks).is_err() {
-            eprintln!("Could not write to {}", &self.path.display());
+    /// Saves `tasks`, recording the changes in the undo journal when `journal`
+    /// is set and they are written to disk.
+    fn persist(&self, tasks: &[TaskItem], journal: bool) {
+        // The changes of a transaction are reported and journaled once, when
+        // it writes them to disk.
+        if let Some(pending) = self.pending.borrow_mut().as_mut() {
+            *pending = tasks.to_vec();
+            return;
         }
+
+        let previous = (self.events || journal).then(|| self.read());
+
+        self.commit(previous.as_deref(), tasks, journal);
     }
 
-    pub fn swap_tasks(&self, id1: u32, id2: u32) {
-        let mut tasks = read_tasks(&self.path).unwrap_or_default();
-        let Some(index1) = tasks.iter().position(|task| task.id == id1) else {
-            eprintln!("Task 1 not found");
+    /// Writes `tasks` to disk, reporting and journaling the changes from
+    /// `previous`, the tasks on disk when known. Nothing is written when
+    /// they didn't change.
+    fn commit(&self, previous: Option<&[TaskItem]>, tasks: &[TaskItem], journal: bool) {
+        // The tasks were read as empty, writing them would lose the store.
+        if self.unreadable.get() {
+            eprintln!("{} was left unchanged", self.path.display());
             return;
-        };
-        let Some(index2) = tasks.iter().position(|task| task.id == id2) else {
-            eprintln!("Task 2 not found");
+        }
+
+        let changes =
+            previous.map(|previous| (diff_tasks(previous, tasks), reorder(previous, tasks)));
+
+        if changes
+            .as_ref()
+            .is_some_and(|(changes, order)| changes.is_empty() && order.is_none())
+        {
+            return;
+        }
+
+        if let Err(error) = self.write(&self.path, tasks) {
+            eprintln!("Could not write to {}: {error:#}", &self.path.display());
+            return;
+        }
+
+        self.writes.set(self.writes.get() + 1);
+
+        let (Some(previous), Some((changes, order))) = (previous, changes) else {
             return;
         };
 
-        tasks[index1].id = id2;
-        tasks[index2].id = id1;
+        if self.events {
+            emit_events(self.operation, previous, tasks);
+        }
 
-        if write_tasks(&self.path, &tasks).is_err() {
-            eprintln!("Could not write to {}", &self.path.display());
+        if journal {
+            self.record(changes, order);
         }
     }
 
-    pub fn reset_tasks(&self, force: bool) {
-        let mut tasks = read_tasks(&self.path).unwrap_or_default();
+    /// Adds `changes` to the undo journal, forgetting what was undone since
+    /// they start a new history.
+    fn record(&self, changes: Vec<Change>, order: Option<Reorder>) {
+        let mut journal = self.read_journal();
+        journal.undo.push(JournalEntry {
+            operation: self.operation,
+            at: Utc::now(),
+            changes,
+            archived: self.archived.take(),
+            order,
+        });
+        journal.redo.clear();
 
-        if tasks.is_empty() {
+        let excess = journal.undo.len().saturating_sub(MAX_JOURNAL_ENTRIES);
+        journal.undo.drain(..excess);
+
+        self.write_journal(&journal);
+    }
+
+    /// Reverts the last recorded operation.
+    pub fn undo(&self) {
+        self.replay(true);
+    }
+
+    /// Reapplies the last undone operation.
+    pub fn redo(&self) {
+        self.replay(false);
+    }
+
+    fn replay(&self, undo: bool) {
+        let mut journal = self.read_journal();
+        let (from, to) = if undo {
+            (&mut journal.undo, &mut journal.redo)
+        } else {
+            (&mut journal.redo, &mut journal.undo)
+        };
+        let Some(entry) = from.pop() else {
+            eprintln!("Nothing to {}", if undo { "undo" } else { "redo" });
             return;
-        }
+        };
 
-        let truncate = force || {
-            println!(
-                "Are your sure you want to permanently delete {} (y/N)?",
-                pluralize(tasks.len(), "task", "tasks")
-            );
+        let mut tasks = self.load();
 
-            let mut input = String::new();
+        println!(
+            "{} the changes of {}:",
+            if undo { "Undid" } else { "Redid" },
+            display_timestamp(&Some(entry.at))
+        );
 
-            if io::stdin().read_line(&mut input).is_err() {
-                eprintln!("Could not read user input");
-                return;
+        for change in &entry.changes {
+            let (before, after) = if undo {
+                (&change.after, &change.before)
+            } else {
+                (&change.before, &change.after)
+            };
+            let uuid = before.as_ref().or(after.as_ref()).map(|task| task.uuid);
+            let position = tasks.iter().position(|task| Some(task.uuid) == uuid);
+
+            match (position, after.clone()) {
+                (Some(index), Some(task)) => tasks[index] = task,
+                (Some(index), None) => {
+                    tasks.remove(index);
+                }
+                (None, Some(mut task)) => {
+                    // Another task may have taken the id in the meantime.
+                    if tasks.iter().any(|other| other.id == task.id) {
+                        task.id = tasks.iter().map(|other| other.id).max().unwrap_or(0) + 1;
+                    }
+
+                    tasks.push(task);
+                }
+                (None, None) => {}
             }
 
-            input.to_lowercase().trim() == "y"
-        };
+            match (before, after) {
+                (_, Some(task)) => {
+                    println!("{} {} {}", display_status(&task.status), task.id, task.task);
+                }
+                (Some(task), None) if entry.archived.contains(&task.uuid) => {
+                    println!("Archived {} {}", task.id, task.task);
+                }
+                (Some(task), None) => println!("Removed {} {}", task.id, task.task),
+                (None, None) => {}
+            }
+        }
 
-        if truncate {
-            tasks.truncate(0);
+        if let Some(order) = &entry.order {
+            let order = if undo { &order.before } else { &order.after };
+            let positions: BTreeMap<Uuid, usize> = order
+                .iter()
+                .enumerate()
+                .map(|(index, uuid)| (*uuid, index))
+                .collect();
+
+            // Tasks added since keep their place after the known ones.
+            tasks.sort_by_key(|task| positions.get(&task.uuid).copied().unwrap_or(usize::MAX));
+
+            println!("Reordered the tasks");
         }
 
-        if write_tasks(&self.path, &tasks).is_err() {
-            eprintln!("Could not write to {}", &self.path.display());
+        // Archived tasks go back to the store on undo, and to the archive on
+        // redo, as they were before being archived. The file they move to is
+        // written first, so an interrupted replay leaves them in both files
+        // for doctor to repair, never in neither.
+        if undo {
+            self.persist(&tasks, false);
         }
-    }
 
-    pub fn infos(&self) {
-        let tasks = read_tasks(&self.path).unwrap_or_default();
-        let done = tasks.iter().filter(|task| task.done).count();
-        let remaining = tasks.len() - done;
+        if !entry.archived.is_empty()
+            && !self.update_archive(|archive| {
+                if undo {
+                    archive.retain(|task| !entry.archived.contains(&task.uuid));
+                } else {
+                    archive.extend(
+                        entry
+                            .changes
+                            .iter()
+                            .filter_map(|change| change.before.clone())
+                            .filter(|task| entry.archived.contains(&task.uuid)),
+                    );
+                }
+            })
+        {
+            return;
+        }
 
-        println!("File location: {}", &self.path.display());
-        println!("Done tasks: {done}");
-        println!("Remaining tasks: {remaining}");
-        println!("Total tasks: {}", tasks.len());
+        if !undo {
+            self.persist(&tasks, false);
+        }
+
+        to.push(entry);
+        self.write_journal(&journal);
     }
-}
 
-fn read_tasks<P: AsRef<Path>>(path: P) -> Result<Vec<TaskItem>> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let tasks = serde_json::from_reader(reader)?;
+    fn read_journal(&self) -> Journal {
+        fs::read(self.journal_path())
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default()
+    }
 
-    Ok(tasks)
-}
+    fn write_journal(&self, journal: &Journal) {
+        let path = self.journal_path();
 
-fn write_tasks<P: AsRef<Path>>(path: P, tasks: &[TaskItem]) -> Result<()> {
-    let file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(path)?;
-    let writer = BufWriter::new(file);
+        match serde_json::to_vec(journal) {
+            Ok(content) if fs::write(&path, &content).is_ok() => {}
+            _ => eprintln!("Could not write to {}", path.display()),
+        }
+    }
 
-    Ok(serde_json::to_writer(writer, &tasks)?)
-}
+    fn journal_path(&self) -> PathBuf {
+        self.path.with_extension("journal.json")
+    }
 
-#[allow(clippy::trivially_copy_pass_by_ref)]
-fn as_checkbox(done: &bool) -> String {
-    let checkbox = if *done { "🗹" } else { "☐" };
+    pub fn add_task(&self, task: impl Into<String>, options: &TaskOptions) {
+        let mut task = task.into();
+        let mut notes = None;
 
-    checkbox.to_string()
+        if let Err(error) = validate_task(&task, &self.config) {
+            let Some(true) = confirm(&format!(
+                "{error}, store it as notes under a short title instead (y/N)?"
+            )) else {
+                eprintln!("Task not added");
+                return;
+            };
+
+            notes = Some(task.trim().to_string());
+            task = short_title(&task);
+        }
+
+        if self.config.normalize {
+            task = normalize_task(&task);
+        }
+
+        let mut tasks = self.load();
+
+        let mut options = options.clone();
+
+        let mut parent_id = None;
+
+        if let Some(parent) = &options.parent {
+            let Some(index) = find_task(&tasks, parent, "Parent task") else {
+                return;
+            };
+            let parent = &tasks[index];
+            parent_id = Some(parent.id);
+
+            // Subtasks inherit what they don't set themselves.
+            options.project = options.project.or_else(|| parent.project.clone());
+            options.location = options.location.or_else(|| parent.location.clone());
+            options.priority = options.priority.or(parent.priority);
+            options.tags.splice(0..0, parent.tags.iter().cloned());
+        }
+
+        let max_id = tasks.iter().map(|task| task.id).max().unwrap_or(0);
+        let options = self.apply_rules(&task, &options);
+        let mut new_task = TaskItem::new(max_id + 1, task, &options);
+
+        new_task.parent = parent_id;
+        new_task.notes = notes;
+
+        let suggestions = if self.config.suggest_tags {
+            suggest_tags(&tasks, &new_task)
+        } else {
+            Vec::new()
+        };
+
+        if !suggestions.is_empty() {
+            println!(
+                "Suggested tags: {} (taskrs tag add {} {})",
+                display_tags(&suggestions),
+                new_task.id,
+                suggestions.join(" ")
+            );
+        }
+
+        tasks.push(new_task);
+
+        self.save(&tasks);
+    }
+
+    fn apply_rules(&self, task: &str, options: &TaskOptions) -> TaskOptions {
+        let mut options = options.clone();
+
+        for rule in self.config.rules.iter().filter(|rule| rule.matches(task)) {
+            options.tags.extend(rule.tags.iter().cloned());
+            options.priority = options.priority.or(rule.priority);
+            options.project = options.project.or_else(|| rule.project.clone());
+        }
+
+        options
+    }
+
+    pub fn test_rules(&self, task: &str) {
+        let rules: Vec<_> = self
+            .config
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.matches(task))
+            .collect();
+
+        if rules.is_empty() {
+            println!("No rule matches");
+            return;
+        }
+
+        for (index, rule) in rules {
+            let mut effects: Vec<String> = rule
+                .tags
+                .iter()
+                .map(|tag| format!("#{}", normalize_tag(tag)))
+                .collect();
+
+            if let Some(priority) = rule.priority {
+                effects.push(format!("priority {priority}"));
+            }
+
+            if let Some(project) = &rule.project {
+                effects.push(format!("project {project}"));
+            }
+
+            println!(
+                "Rule {} ({}): {}",
+                index + 1,
+                rule.pattern,
+                effects.join(", ")
+            );
+        }
+    }
+
+    /// Applies the aging rules of the configuration to the open tasks old
+    /// enough for them, reporting each change on stderr. The changes are
+    /// journaled like the others.
+    pub fn age_tasks(&self) {
+        if self.config.aging.is_empty() {
+            return;
+        }
+
+        let mut tasks = self.load();
+        let now = Utc::now();
+        let mut aged = false;
+
+        for task in tasks.iter_mut().filter(|task| task.is_open()) {
+            let Some(created_at) = task.created_at else {
+                continue;
+            };
+
+            for rule in &self.config.aging {
+                if task.escalations.contains(&rule.name)
+                    || now - created_at < TimeDelta::days(i64::from(rule.days))
+                    || rule.tag.as_ref().is_some_and(|tag| !task.has_tag(tag))
+                {
+                    continue;
+                }
+
+                let mut effects = Vec::new();
+
+                if rule.raise_priority {
+                    let priority = match task.priority {
+                        None => Priority::Low,
+                        Some(Priority::Low) => Priority::Medium,
+                        Some(Priority::Medium | Priority::High) => Priority::High,
+                    };
+
+                    task.priority = Some(priority);
+                    effects.push(format!("priority {priority}"));
+                }
+
+                if let Some(tag) = &rule.add_tag {
+                    task.add_tags(std::slice::from_ref(tag));
+                    effects.push(format!("#{}", normalize_tag(tag)));
+                }
+
+                task.escalations.push(rule.name.clone());
+                aged = true;
+
+                if !self.events {
+                    eprintln!(
+                        "Aging rule {}: {} {} ({})",
+                        rule.name,
+                        task.id,
+                        task.task,
+                        effects.join(", ")
+                    );
+                }
+            }
+        }
+
+        if aged {
+            self.save(&tasks);
+        }
+    }
+
+    /// Prints a one-line warning on stderr when tasks are overdue or due
+    /// within the `nag` days of the configuration.
+    pub fn nag(&self) {
+        let Some(days) = self.config.nag else {
+            return;
+        };
+        let today = today();
+        let soon = today
+            .checked_add_days(Days::new(u64::from(days)))
+            .unwrap_or(NaiveDate::MAX);
+        let mut overdue = 0;
+        let mut due_soon = 0;
+
+        let complete = self.scan(|task| {
+            if task.is_overdue(today) {
+                overdue += 1;
+            }
+
+            if task.is_open() && task.due.is_some_and(|due| due >= today && due <= soon) {
+                due_soon += 1;
+            }
+        });
+
+        if !complete {
+            return;
+        }
+
+        let mut warnings = Vec::new();
+
+        if overdue > 0 {
+            warnings.push(format!("{} overdue", pluralize(overdue, "task", "tasks")));
+        }
+
+        if due_soon > 0 {
+            let window = match days {
+                0 => "today".to_string(),
+                _ => format!("within {}", pluralize(days as usize, "day", "days")),
+            };
+
+            warnings.push(format!(
+                "{} due {window}",
+                pluralize(due_soon, "task", "tasks")
+            ));
+        }
+
+        if !warnings.is_empty() {
+            eprintln!("⚠ {} — run `taskrs list --sort due`", warnings.join(", "));
+        }
+    }
+
+    pub fn list_tasks(&self, options: &ListOptions) {
+        let options = &ListOptions {
+            sort: Some(options.sort.unwrap_or(self.config.sort)),
+            summary: options.summary || self.config.summary,
+            ..options.clone()
+        };
+
+        // Read apart from the cached table, which only tracks the tasks.
+        if let Some(pinned) = self.pinned().filter(|_| !options.archived) {
+            println!("{pinned}\n");
+        }
+
+        print!(
+            "{}",
+            self.cached(format!("list {options:?}"), || self.render_list(options))
+        );
+    }
+
+    fn render_list(&self, options: &ListOptions) -> String {
+        let mut output = String::new();
+        let mut stored = if options.archived {
+            read_tasks(self.archive_path()).unwrap_or_default()
+        } else {
+            self.load()
+        };
+        mark_blocked(&mut stored);
+        let today = today();
+        let sort = options.sort.unwrap_or_default();
+        let mut tasks = filter_tasks(&stored, options, today);
+
+        if sort != SortKey::Manual {
+            tasks.sort_by_key(|task| task.id);
+        }
+
+        tasks.sort_by(|a, b| sort.compare(a, b));
+
+        if options.reverse {
+            tasks.reverse();
+        }
+
+        let estimate: u32 = tasks.iter().filter_map(|task| task.estimate).sum();
+
+        output.push_str(&if options.checklist {
+            render_checklist(&tasks)
+        } else {
+            render_tables(tasks, options)
+        });
+
+        if options.summary {
+            // Counted over the tasks matching the filters, closed ones
+            // included for the progress.
+            let matching = filter_tasks(
+                &stored,
+                &ListOptions {
+                    all: true,
+                    ..options.clone()
+                },
+                today,
+            );
+            let count = |predicate: fn(&TaskItem, NaiveDate) -> bool| {
+                matching
+                    .iter()
+                    .filter(|task| predicate(task, today))
+                    .count()
+            };
+            let done = count(|task, _| task.status == Status::Done);
+
+            let _ = write!(
+                output,
+                "\n{} pending · {} due today · {} overdue · {} done · {}% done",
+                count(|task, _| task.is_open()),
+                count(TaskItem::is_due_today),
+                count(TaskItem::is_overdue),
+                done,
+                percentage(done, matching.len())
+            );
+
+            if estimate > 0 {
+                let _ = write!(output, " · {} estimated", display_estimate(&Some(estimate)));
+            }
+
+            output.push('\n');
+        }
+
+        output
+    }
+
+    /// Lists the tasks whose text or notes contain `query`, ignoring case, or
+    /// match it as a regular expression. Only `all` and `width` of `options`
+    /// are used.
+    pub fn search_tasks(&self, query: &str, regex: bool, options: &ListOptions) {
+        let pattern = if regex {
+            match Regex::new(query) {
+                Ok(pattern) => Some(pattern),
+                Err(error) => {
+                    eprintln!("Invalid regular expression: {error}");
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+        let query = query.to_lowercase();
+        let matches = |text: &str| {
+            pattern.as_ref().map_or_else(
+                || text.to_lowercase().contains(&query),
+                |pattern| pattern.is_match(text),
+            )
+        };
+
+        let mut tasks = self.load();
+        mark_blocked(&mut tasks);
+        let mut tasks: Vec<&TaskItem> = tasks
+            .iter()
+            .filter(|task| options.all || task.is_open())
+            .filter(|task| matches(&task.task) || task.notes.as_deref().is_some_and(matches))
+            .collect();
+        tasks.sort_by_key(|task| task.id);
+
+        if tasks.is_empty() {
+            eprintln!("No matching task");
+            return;
+        }
+
+        println!("{}", render_table(&tasks, options));
+    }
+
+    pub fn list_projects(&self) {
+        let tasks = self.load();
+        let mut projects: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+
+        for task in &tasks {
+            if let Some(project) = &task.project {
+                let (pending, done) = projects.entry(project).or_default();
+
+                if task.is_open() {
+                    *pending += 1;
+                } else if task.status == Status::Done {
+                    *done += 1;
+                }
+            }
+        }
+
+        for (project, (pending, done)) in projects {
+            println!("{project}: {pending} pending, {done} done");
+        }
+    }
+
+    pub fn random_task(&self, filter: Option<&str>) {
+        let tasks = self.load();
+        let filter = filter.map(str::to_lowercase);
+        let candidates: Vec<&TaskItem> = tasks
+            .iter()
+            .filter(|task| task.is_open())
+            .filter(|task| {
+                filter
+                    .as_ref()
+                    .is_none_or(|filter| task.task.to_lowercase().contains(filter))
+            })
+            .collect();
+
+        if candidates.is_empty() {
+            eprintln!("No matching task");
+            return;
+        }
+
+        let task = candidates[fastrand::usize(..candidates.len())];
+
+        println!("{} {}", task.id, task.task);
+    }
+
+    /// Prints the most urgent tasks estimated to fit in `minutes`, among the
+    /// ones matching `options`. Bigger tasks win ties to make the most of
+    /// the time available.
+    pub fn suggest_tasks(&self, minutes: u32, count: usize, options: &ListOptions) {
+        let mut tasks = self.load();
+        mark_blocked(&mut tasks);
+        let today = today();
+        let options = ListOptions {
+            fits: Some(minutes),
+            ..options.clone()
+        };
+        let mut candidates: Vec<&TaskItem> = filter_tasks(&tasks, &options, today)
+            .into_iter()
+            .filter(|task| task.is_open() && !task.blocked)
+            .collect();
+
+        if candidates.is_empty() {
+            eprintln!(
+                "No estimated task fits in {}",
+                display_estimate(&Some(minutes))
+            );
+            return;
+        }
+
+        candidates.sort_by_key(|task| {
+            (
+                Reverse(task.urgency(today)),
+                Reverse(task.estimate),
+                task.id,
+            )
+        });
+
+        for task in candidates.into_iter().take(count) {
+            print!(
+                "{} {} ({}",
+                task.id,
+                task.task,
+                display_estimate(&task.estimate)
+            );
+
+            if let Some(due) = task.due {
+                print!(", due {due}");
+            }
+
+            println!(")");
+        }
+    }
+
+    pub fn update_task(&self, id: &TaskRef, task: impl Into<String>) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let current = &mut tasks[index];
+
+        current.task = task.into();
+
+        self.save(&tasks);
+    }
+
+    /// Opens the task in `$VISUAL` or `$EDITOR`, its text on the first line
+    /// and its notes after a blank line, and saves what was written.
+    pub fn edit_task(&self, id: &TaskRef) {
+        let tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let task = &tasks[index];
+        let uuid = task.uuid;
+        // Named at random and never opened if it exists, so another user of
+        // the shared directory cannot plant a file or symlink to be written.
+        let path = env::temp_dir().join(format!("taskrs-{}.txt", Uuid::new_v4().simple()));
+        let mut content = format!("{}\n", task.task);
+
+        if let Some(notes) = &task.notes {
+            let _ = write!(content, "\n{notes}\n");
+        }
+
+        let written = create_private(&path).and_then(|mut file| file.write_all(content.as_bytes()));
+
+        if written.is_err() {
+            eprintln!("Could not write {}", path.display());
+            return;
+        }
+
+        let saved = open_editor(&path);
+        let edited = fs::read_to_string(&path);
+        let _ = fs::remove_file(&path);
+
+        if !saved {
+            eprintln!("The task was not changed");
+            return;
+        }
+
+        let Ok(edited) = edited else {
+            eprintln!("Could not read the edited task");
+            return;
+        };
+        let (text, notes) = edited.split_once('\n').unwrap_or((&edited, ""));
+        let text = text.trim();
+        let notes = notes.trim_start_matches('\n').trim_end();
+
+        if text.is_empty() {
+            eprintln!("Task text cannot be empty, the task was not changed");
+            return;
+        }
+
+        // The store may have changed while the editor was open.
+        let mut tasks = self.load();
+        let Some(current) = tasks.iter_mut().find(|task| task.uuid == uuid) else {
+            eprintln!("Task was removed while editing");
+            return;
+        };
+
+        text.clone_into(&mut current.task);
+        current.notes = Some(notes.to_string()).filter(|notes| !notes.is_empty());
+
+        self.save(&tasks);
+    }
+
+    pub fn note_task(&self, id: &TaskRef, notes: &str, append: bool) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let current = &mut tasks[index];
+
+        let notes = match (current.notes.take(), append) {
+            (Some(existing), true) => format!("{existing}\n{notes}"),
+            _ => notes.to_string(),
+        };
+
+        current.notes = Some(notes).filter(|notes| !notes.trim().is_empty());
+
+        self.save(&tasks);
+    }
+
+    pub fn show_task(&self, id: &TaskRef) {
+        let tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let task = &tasks[index];
+
+        println!("{} {} {}", display_status(&task.status), task.id, task.task);
+        println!("UUID: {}", task.uuid);
+
+        if let Some(project) = &task.project {
+            println!("Project: {project}");
+        }
+
+        if let Some(assignee) = &task.assignee {
+            println!("Assignee: {assignee}");
+        }
+
+        if let Some(location) = &task.location {
+            println!("Location: {location}");
+        }
+
+        if task.ephemeral {
+            println!("Ephemeral: removed after today");
+        }
+
+        if let Some(priority) = task.priority {
+            println!("Priority: {priority}");
+        }
+
+        if let Some(due) = task.due {
+            println!("Due: {due}");
+        }
+
+        if task.estimate.is_some() {
+            println!("Estimate: {}", display_estimate(&task.estimate));
+        }
+
+        if let Some(url) = &task.url {
+            println!("URL: {url}");
+        }
+
+        if let Some(wait_until) = task.wait_until {
+            println!("Waiting until: {wait_until}");
+        }
+
+        if !task.reminders.is_empty() {
+            let reminders: Vec<String> = task
+                .reminders
+                .iter()
+                .map(|at| display_timestamp(&Some(*at)))
+                .collect();
+
+            println!("Reminders: {}", reminders.join(", "));
+        }
+
+        if !task.tags.is_empty() {
+            println!("Tags: {}", display_tags(&task.tags));
+        }
+
+        if task.points > 0 && self.config.points {
+            println!("Points: {}", task.points);
+        }
+
+        if task.created_at.is_some() {
+            println!("Created: {}", display_timestamp(&task.created_at));
+        }
+
+        if task.completed_at.is_some() {
+            println!("Completed: {}", display_timestamp(&task.completed_at));
+        }
+
+        if !task.time_entries.is_empty() {
+            println!(
+                "Tracked: {}{}",
+                display_duration(task.tracked_time(Utc::now())),
+                if task.is_running() { " (running)" } else { "" }
+            );
+        }
+
+        if !task.depends_on.is_empty() {
+            let ids: Vec<String> = task.depends_on.iter().map(u32::to_string).collect();
+
+            println!("Depends on: {}", ids.join(", "));
+        }
+
+        for (key, value) in &task.meta {
+            println!("{key}: {value}");
+        }
+
+        if !task.checklist.is_empty() {
+            println!("Checklist: {}", display_checklist(&task.checklist));
+        }
+
+        for (number, item) in task.checklist.iter().enumerate() {
+            let status = if item.done {
+                Status::Done
+            } else {
+                Status::Todo
+            };
+
+            println!(
+                "  {}. {} {}",
+                number + 1,
+                display_status(&status),
+                item.text
+            );
+        }
+
+        if let Some(notes) = &task.notes {
+            println!("\n{notes}");
+        }
+    }
+
+    /// Marks the selected tasks, writing the store once. Text selectors only
+    /// pick tasks that don't already have `status`.
+    pub fn mark_tasks(&self, selectors: &[Selector], status: Status, recursive: bool) {
+        self.transaction(|store| {
+            let ids = select_tasks(&store.load(), selectors, |task| task.status != status);
+
+            for id in ids {
+                store.mark_task(&TaskRef::Id(id), status, recursive);
+            }
+        });
+    }
+
+    /// Marks the only pending task whose text contains `text`, listing the
+    /// candidates when several do.
+    pub fn mark_matching(&self, text: &str, status: Status, recursive: bool) {
+        let tasks = self.load();
+        let needle = text.to_lowercase();
+        let candidates: Vec<&TaskItem> = tasks
+            .iter()
+            .filter(|task| task.is_open() && task.task.to_lowercase().contains(&needle))
+            .collect();
+
+        match candidates[..] {
+            [] => eprintln!("No pending task matches \"{text}\""),
+            [task] => self.mark_task(&TaskRef::Id(task.id), status, recursive),
+            _ => {
+                eprintln!("Several pending tasks match \"{text}\":");
+
+                for task in candidates {
+                    eprintln!("  {} {}", task.id, task.task);
+                }
+            }
+        }
+    }
+
+    pub fn mark_task(&self, id: &TaskRef, status: Status, recursive: bool) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let id = tasks[index].id;
+
+        let mut ids = vec![id];
+
+        if recursive {
+            ids.extend(descendants(&tasks, id));
+        }
+
+        let now = Utc::now();
+        let mut moved = Vec::new();
+
+        for task in tasks.iter_mut().filter(|task| ids.contains(&task.id)) {
+            let was_open = task.is_open();
+
+            if task.status != status {
+                moved.push(task.id);
+            }
+
+            task.status = status;
+
+            if was_open != task.is_open() {
+                task.completed_at = (!task.is_open()).then_some(now);
+            }
+
+            if !task.is_open() {
+                task.focused = false;
+                task.stop_timer(now);
+            }
+        }
+
+        if status.is_closed() {
+            mark_blocked(&mut tasks);
+
+            for task in tasks.iter().filter(|task| {
+                task.is_open() && !task.blocked && task.depends_on.iter().any(|id| ids.contains(id))
+            }) {
+                println!("Unblocked: {} {}", task.id, task.task);
+            }
+        }
+
+        if !self.check_wip_limits(&tasks, &moved) {
+            return;
+        }
+
+        self.save(&tasks);
+    }
+
+    /// Checks the work-in-progress limits of the configuration once the
+    /// tasks `moved` changed status, warning about the limits they went
+    /// over. Returns `false` when one of them blocks the change.
+    fn check_wip_limits(&self, tasks: &[TaskItem], moved: &[u32]) -> bool {
+        let mut allowed = true;
+
+        for limit in &self.config.wip_limits {
+            let counts = |task: &TaskItem| {
+                task.status == limit.status
+                    && limit.tag.as_ref().is_none_or(|tag| task.has_tag(tag))
+            };
+
+            if !tasks
+                .iter()
+                .any(|task| moved.contains(&task.id) && counts(task))
+            {
+                continue;
+            }
+
+            let count = tasks.iter().filter(|task| counts(task)).count();
+
+            if count <= limit.max {
+                continue;
+            }
+
+            let tag = limit.tag.as_ref().map_or_else(String::new, |tag| {
+                format!(" tagged #{}", normalize_tag(tag))
+            });
+
+            eprintln!(
+                "Over the WIP limit: {}{tag}, the limit is {}{}",
+                pluralize(
+                    count,
+                    &format!("{} task", limit.status),
+                    &format!("{} tasks", limit.status)
+                ),
+                limit.max,
+                if limit.block {
+                    ", nothing was changed"
+                } else {
+                    ""
+                }
+            );
+
+            allowed &= !limit.block;
+        }
+
+        allowed
+    }
+
+    /// Goes through the overdue tasks, offering to move each to today,
+    /// tomorrow or next week with the work already due then, or to drop it,
+    /// and applies the answers in a single write at the end.
+    pub fn triage(&self) {
+        let tasks = self.load();
+        let today = today();
+        let mut overdue: Vec<&TaskItem> =
+            tasks.iter().filter(|task| task.is_overdue(today)).collect();
+
+        if overdue.is_empty() {
+            println!("No overdue task");
+            return;
+        }
+
+        overdue.sort_by_key(|task| (task.due, task.id));
+
+        // Tasks and minutes of work due each day.
+        let mut load: BTreeMap<NaiveDate, (usize, u32)> = BTreeMap::new();
+
+        for task in tasks.iter().filter(|task| task.is_open()) {
+            if let Some(due) = task.due {
+                let (count, minutes) = load.entry(due).or_default();
+                *count += 1;
+                *minutes += task.estimate.unwrap_or(0);
+            }
+        }
+
+        let days = [
+            ("t", "[t]oday", Some(today)),
+            ("m", "to[m]orrow", today.checked_add_days(Days::new(1))),
+            ("n", "[n]ext week", today.checked_add_days(Days::new(7))),
+        ];
+        let mut moves: Vec<(Uuid, NaiveDate)> = Vec::new();
+        let mut drops: Vec<Uuid> = Vec::new();
+
+        for task in overdue {
+            let late = (today - task.due.unwrap_or(today)).num_days();
+            let choices: Vec<String> = days
+                .iter()
+                .filter_map(|(_, name, day)| {
+                    let (count, minutes) = load.get(&(*day)?).copied().unwrap_or_default();
+                    let work = if minutes > 0 {
+                        format!(", {}", display_estimate(&Some(minutes)))
+                    } else {
+                        String::new()
+                    };
+
+                    Some(format!("{name} ({count} due{work})"))
+                })
+                .collect();
+
+            println!(
+                "{} {} (due {}, {} late)",
+                task.id,
+                task.task,
+                task.due.unwrap_or(today),
+                pluralize(usize::try_from(late).unwrap_or_default(), "day", "days")
+            );
+
+            let Some(answer) = prompt(&format!(
+                "{}, [d]rop, [s]kip or [q]uit (t/m/n/d/S/q)?",
+                choices.join(", ")
+            )) else {
+                return;
+            };
+
+            if let Some(day) = days
+                .iter()
+                .find(|(key, _, _)| *key == answer)
+                .and_then(|(_, _, day)| *day)
+            {
+                let (count, minutes) = load.entry(day).or_default();
+                *count += 1;
+                *minutes += task.estimate.unwrap_or(0);
+                moves.push((task.uuid, day));
+            } else if answer == "d" {
+                drops.push(task.uuid);
+            } else if answer == "q" {
+                break;
+            }
+        }
+
+        if moves.is_empty() && drops.is_empty() {
+            return;
+        }
+
+        self.transaction(|store| {
+            let mut tasks = store.load();
+
+            for task in &mut tasks {
+                if let Some((_, day)) = moves.iter().find(|(uuid, _)| *uuid == task.uuid) {
+                    task.due = Some(*day);
+                }
+            }
+
+            let dropped: Vec<u32> = tasks
+                .iter()
+                .filter(|task| drops.contains(&task.uuid))
+                .map(|task| task.id)
+                .collect();
+
+            store.save(&tasks);
+
+            for id in dropped {
+                store.mark_task(&TaskRef::Id(id), Status::Cancelled, false);
+            }
+        });
+
+        println!(
+            "Rescheduled {}, dropped {}",
+            pluralize(moves.len(), "task", "tasks"),
+            drops.len()
+        );
+    }
+
+    pub fn prioritize_task(&self, id: &TaskRef, priority: Option<Priority>) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let current = &mut tasks[index];
+
+        let previous = current.priority;
+        current.priority = priority;
+
+        // Subtasks that inherited the previous priority follow the change.
+        let mut queue = vec![current.id];
+
+        while let Some(parent) = queue.pop() {
+            for task in tasks
+                .iter_mut()
+                .filter(|task| task.parent == Some(parent) && task.priority == previous)
+            {
+                task.priority = priority;
+                queue.push(task.id);
+            }
+        }
+
+        self.save(&tasks);
+    }
+
+    /// Tags the selected tasks, writing the store once.
+    pub fn tag_tasks(&self, selectors: &[Selector], tags: &[String], propagate: bool) {
+        self.transaction(|store| {
+            let ids = select_tasks(&store.load(), selectors, TaskItem::is_open);
+
+            for id in ids {
+                store.tag_task(&TaskRef::Id(id), tags, propagate);
+            }
+        });
+    }
+
+    /// Removes tags from the selected tasks, writing the store once.
+    pub fn untag_tasks(&self, selectors: &[Selector], tags: &[String], propagate: bool) {
+        self.transaction(|store| {
+            let ids = select_tasks(&store.load(), selectors, TaskItem::is_open);
+
+            for id in ids {
+                store.untag_task(&TaskRef::Id(id), tags, propagate);
+            }
+        });
+    }
+
+    /// Adds `tags` to a task, and to its subtasks when `propagate` is set.
+    pub fn tag_task(&self, id: &TaskRef, tags: &[String], propagate: bool) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let current = &mut tasks[index];
+
+        current.add_tags(tags);
+
+        let descendants = if propagate {
+            descendants(&tasks, tasks[index].id)
+        } else {
+            Vec::new()
+        };
+
+        for task in tasks
+            .iter_mut()
+            .filter(|task| descendants.contains(&task.id))
+        {
+            task.add_tags(tags);
+        }
+
+        self.save(&tasks);
+    }
+
+    /// Removes `tags` from a task, and from its subtasks when `propagate` is
+    /// set.
+    pub fn untag_task(&self, id: &TaskRef, tags: &[String], propagate: bool) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let current = &mut tasks[index];
+
+        current.remove_tags(tags);
+
+        let descendants = if propagate {
+            descendants(&tasks, tasks[index].id)
+        } else {
+            Vec::new()
+        };
+
+        for task in tasks
+            .iter_mut()
+            .filter(|task| descendants.contains(&task.id))
+        {
+            task.remove_tags(tags);
+        }
+
+        self.save(&tasks);
+    }
+
+    pub fn add_check(&self, id: &TaskRef, text: &str) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+
+        tasks[index].checklist.push(ChecklistItem {
+            text: text.to_string(),
+            done: false,
+        });
+
+        self.save(&tasks);
+    }
+
+    /// Checks or unchecks the checklist item at `number`, starting from 1.
+    pub fn mark_check(&self, id: &TaskRef, number: usize, done: bool) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let Some(item) = number
+            .checked_sub(1)
+            .and_then(|number| tasks[index].checklist.get_mut(number))
+        else {
+            eprintln!("Checklist item not found");
+            return;
+        };
+
+        item.done = done;
+
+        self.save(&tasks);
+    }
+
+    /// Opens the URL of a task with the desktop's default handler.
+    pub fn open_task(&self, id: &TaskRef) {
+        let tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let Some(url) = &tasks[index].url else {
+            eprintln!("Task has no URL");
+            return;
+        };
+
+        // Not through `cmd /C start`, which would read `&` and `|` of the URL
+        // as shell operators.
+        let mut command = if cfg!(target_os = "windows") {
+            let mut command = process::Command::new("rundll32");
+            command.arg("url.dll,FileProtocolHandler");
+            command
+        } else if cfg!(target_os = "macos") {
+            process::Command::new("open")
+        } else {
+            process::Command::new("xdg-open")
+        };
+
+        if command.arg(url).spawn().is_err() {
+            eprintln!("Could not open {url}");
+        }
+    }
+
+    pub fn set_meta(&self, id: &TaskRef, key: &str, value: &str) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+
+        tasks[index].meta.insert(key.to_string(), value.to_string());
+
+        self.save(&tasks);
+    }
+
+    /// Prints the value of `key`, or every key and value when `key` is `None`.
+    pub fn get_meta(&self, id: &TaskRef, key: Option<&str>) {
+        let tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let meta = &tasks[index].meta;
+
+        let Some(key) = key else {
+            for (key, value) in meta {
+                println!("{key}={value}");
+            }
+
+            return;
+        };
+
+        match meta.get(key) {
+            Some(value) => println!("{value}"),
+            None => eprintln!("No `{key}` metadata on this task"),
+        }
+    }
+
+    pub fn delete_meta(&self, id: &TaskRef, key: &str) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+
+        if tasks[index].meta.remove(key).is_none() {
+            eprintln!("No `{key}` metadata on this task");
+            return;
+        }
+
+        self.save(&tasks);
+    }
+
+    pub fn assign_task(&self, id: &TaskRef, assignee: Option<String>) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+
+        tasks[index].assignee = assignee.filter(|assignee| !assignee.trim().is_empty());
+
+        self.save(&tasks);
+    }
+
+    pub fn estimate_task(&self, id: &TaskRef, estimate: Option<u32>) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+
+        tasks[index].estimate = estimate;
+
+        self.save(&tasks);
+    }
+
+    pub fn focus_task(&self, id: &TaskRef) {
+        let mut tasks = self.load();
+
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let id = tasks[index].id;
+
+        let now = Utc::now();
+
+        for task in &mut tasks {
+            task.focused = task.id == id;
+
+            // Focusing also moves the running timer to the focused task.
+            if task.focused && !task.is_running() {
+                task.time_entries.push(TimeEntry {
+                    start: now,
+                    end: None,
+                });
+            } else if !task.focused {
+                task.stop_timer(now);
+            }
+        }
+
+        self.save(&tasks);
+    }
+
+    /// Prints the id and text of the focused task, if any, for prompts and
+    /// status bars.
+    pub fn print_focus(&self) {
+        let focus = self.cached("focus".to_string(), || {
+            let mut focus = String::new();
+
+            let complete = self.scan(|task| {
+                if task.focused && task.is_open() {
+                    focus = format!("{} {}\n", task.id, task.task);
+                }
+            });
+
+            if complete {
+                focus
+            } else {
+                String::new()
+            }
+        });
+
+        print!("{focus}");
+    }
+
+    pub fn defer_task(&self, id: &TaskRef, until: Option<NaiveDate>) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let current = &mut tasks[index];
+
+        current.wait_until = until;
+
+        self.save(&tasks);
+    }
+
+    pub fn add_reminder(&self, id: &TaskRef, at: DateTime<Utc>) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+
+        tasks[index].add_reminder(at);
+
+        self.save(&tasks);
+    }
+
+    /// Adds a reminder `minutes` before the task is due, due dates meaning
+    /// 9:00 like in [`parse_moment`]. The reminder stays where it is if the
+    /// due date changes.
+    pub fn remind_before_due(&self, id: &TaskRef, minutes: u32) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let Some(due) = tasks[index].due else {
+            eprintln!("Task has no due date");
+            return;
+        };
+        let Some(at) = due
+            .and_time(DEFAULT_TIME)
+            .and_local_timezone(Local)
+            .earliest()
+            .map(|due| due.to_utc() - TimeDelta::minutes(i64::from(minutes)))
+        else {
+            eprintln!("Invalid due date {due}");
+            return;
+        };
+
+        tasks[index].add_reminder(at);
+
+        self.save(&tasks);
+    }
+
+    pub fn clear_reminders(&self, id: &TaskRef) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+
+        tasks[index].reminders.clear();
+
+        self.save(&tasks);
+    }
+
+    /// Prints the pending tasks with a reminder due and removes the due
+    /// reminders, so that running this from cron reports each of them once.
+    pub fn remind(&self) {
+        let mut tasks = self.load();
+        let now = Utc::now();
+        let mut reminded = false;
+
+        for task in tasks
+            .iter_mut()
+            .filter(|task| task.is_open() && task.reminders.first().is_some_and(|at| *at <= now))
+        {
+            let due = task
+                .reminders
+                .iter()
+                .filter(|at| **at <= now)
+                .max()
+                .copied();
+
+            println!("⏰ {} {} ({})", task.id, task.task, display_timestamp(&due));
+            task.reminders.retain(|at| *at > now);
+            reminded = true;
+        }
+
+        if reminded {
+            self.save(&tasks);
+        }
+    }
+
+    pub fn start_task(&self, id: &TaskRef) {
+        let mut tasks = self.load();
+
+        if let Some(running) = tasks.iter().find(|task| task.is_running()) {
+            eprintln!("Task {} is already running, stop it first", running.id);
+            return;
+        }
+
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let current = &mut tasks[index];
+
+        current.time_entries.push(TimeEntry {
+            start: Utc::now(),
+            end: None,
+        });
+
+        // Working on a task puts it in progress, within the WIP limits.
+        if matches!(current.status, Status::Todo | Status::Waiting) {
+            current.status = Status::InProgress;
+
+            let id = current.id;
+
+            if !self.check_wip_limits(&tasks, &[id]) {
+                return;
+            }
+        }
+
+        self.save(&tasks);
+    }
+
+    pub fn stop_task(&self) {
+        let mut tasks = self.load();
+        let Some(running) = tasks.iter_mut().find(|task| task.is_running()) else {
+            eprintln!("No task is running");
+            return;
+        };
+
+        running.stop_timer(Utc::now());
+
+        self.save(&tasks);
+    }
+
+    pub fn task_times(&self, id: &TaskRef) {
+        let tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let task = &tasks[index];
+        let now = Utc::now();
+
+        for entry in &task.time_entries {
+            let end = entry.end.map_or_else(
+                || "running".to_string(),
+                |end| display_timestamp(&Some(end)),
+            );
+
+            println!(
+                "{} → {}  {}",
+                display_timestamp(&Some(entry.start)),
+                end,
+                display_duration(entry.end.unwrap_or(now) - entry.start)
+            );
+        }
+
+        println!("Total: {}", display_duration(task.tracked_time(now)));
+    }
+
+    pub fn block_task(&self, id: &TaskRef, on: &TaskRef) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let Some(on) = find_task(&tasks, on, "Blocking task").map(|index| tasks[index].id) else {
+            return;
+        };
+        let id = tasks[index].id;
+
+        if id == on || dependencies(&tasks, on).contains(&id) {
+            eprintln!("Task {id} cannot depend on task {on}, this would create a cycle");
+            return;
+        }
+
+        let current = &mut tasks[index];
+
+        if !current.depends_on.contains(&on) {
+            current.depends_on.push(on);
+        }
+
+        self.save(&tasks);
+    }
+
+    pub fn unblock_task(&self, id: &TaskRef, on: Option<&TaskRef>) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let on = match on {
+            Some(on) => match find_task(&tasks, on, "Blocking task") {
+                Some(on) => Some(tasks[on].id),
+                None => return,
+            },
+            None => None,
+        };
+        let current = &mut tasks[index];
+
+        current
+            .depends_on
+            .retain(|dependency| on.is_some_and(|on| on != *dependency));
+
+        self.save(&tasks);
+    }
+
+    /// Deletes the selected tasks, writing the store once. Asks for a
+    /// confirmation when text selectors picked several tasks.
+    pub fn delete_tasks(&self, selectors: &[Selector], mode: Option<DeleteMode>) {
+        self.transaction(|store| {
+            let tasks = store.load();
+            let ids = select_tasks(&tasks, selectors, |_| true);
+
+            if ids.len() > 1
+                && selectors
+                    .iter()
+                    .any(|selector| matches!(selector, Selector::Matching(_)))
+            {
+                for task in tasks.iter().filter(|task| ids.contains(&task.id)) {
+                    println!("{} {}", task.id, task.task);
+                }
+
+                let Some(true) = confirm(&format!("Delete these {} tasks (y/N)?", ids.len()))
+                else {
+                    eprintln!("No task deleted");
+                    return;
+                };
+            }
+
+            for id in ids {
+                // Cascading deletes may have removed it already.
+                if store.load().iter().any(|task| task.id == id) {
+                    store.delete_task(&TaskRef::Id(id), mode);
+                }
+            }
+        });
+    }
+
+    pub fn delete_task(&self, id: &TaskRef, mode: Option<DeleteMode>) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let id = tasks[index].id;
+
+        let children = tasks.iter().filter(|task| task.parent == Some(id)).count();
+        let dependents = tasks
+            .iter()
+            .filter(|task| task.depends_on.contains(&id))
+            .count();
+
+        let mode = if children == 0 && dependents == 0 {
+            DeleteMode::Reassign
+        } else if let Some(mode) = mode {
+            mode
+        } else {
+            let answer = prompt(&format!(
+                "Task {id} has {} and {}. [c]ascade delete, [o]rphan them, [r]eassign them to its parent or abort (c/o/r/N)?",
+                pluralize(children, "subtask", "subtasks"),
+                pluralize(dependents, "dependent task", "dependent tasks"),
+            ));
+
+            match answer.as_deref() {
+                Some("c") => DeleteMode::Cascade,
+                Some("o") => DeleteMode::Orphan,
+                Some("r") => DeleteMode::Reassign,
+                _ => return,
+            }
+        };
+
+        let removed = tasks.remove(index);
+        let mut deleted = vec![id];
+
+        if mode == DeleteMode::Cascade {
+            deleted.extend(descendants(&tasks, id));
+            tasks.retain(|task| !deleted.contains(&task.id));
+        }
+
+        for task in &mut tasks {
+            if task.parent == Some(id) {
+                task.parent = match mode {
+                    DeleteMode::Reassign => removed.parent,
+                    DeleteMode::Orphan | DeleteMode::Cascade => None,
+                };
+            }
+
+            if mode == DeleteMode::Reassign && task.depends_on.contains(&id) {
+                for dependency in &removed.depends_on {
+                    if !task.depends_on.contains(dependency) && *dependency != task.id {
+                        task.depends_on.push(*dependency);
+                    }
+                }
+            }
+
+            task.depends_on
+                .retain(|dependency| !deleted.contains(dependency));
+        }
+
+        self.save(&tasks);
+    }
+
+    /// Repairs references to tasks that no longer exist.
+    pub fn doctor(&self) {
+        let mut tasks = self.load();
+        let mut repairs = 0;
+
+        for path in [&self.path, &self.archive_path()] {
+            for temporary in temporary_files(path) {
+                if fs::remove_file(&temporary).is_ok() {
+                    println!(
+                        "Removed {}, left by an interrupted write",
+                        temporary.display()
+                    );
+                }
+            }
+        }
+
+        // Left by an archive interrupted between writing the archive and the
+        // store.
+        let archived: BTreeSet<Uuid> = read_tasks(self.archive_path())
+            .map(|archive| archive.iter().map(|task| task.uuid).collect())
+            .unwrap_or_default();
+
+        tasks.retain(|task| {
+            let duplicate = archived.contains(&task.uuid);
+
+            if duplicate {
+                println!("Task {}: removed, it is already archived", task.id);
+                repairs += 1;
+            }
+
+            !duplicate
+        });
+
+        let ids: Vec<u32> = tasks.iter().map(|task| task.id).collect();
+
+        for task in &mut tasks {
+            if task
+                .parent
+                .is_some_and(|parent| parent == task.id || !ids.contains(&parent))
+            {
+                println!("Task {}: removed missing parent", task.id);
+                task.parent = None;
+                repairs += 1;
+            }
+
+            let before = task.depends_on.len();
+            let id = task.id;
+            task.depends_on
+                .retain(|dependency| *dependency != id && ids.contains(dependency));
+
+            if task.depends_on.len() < before {
+                println!("Task {}: removed missing dependencies", task.id);
+                repairs += 1;
+            }
+        }
+
+        let mut seen = Vec::with_capacity(ids.len());
+
+        for id in &ids {
+            if seen.contains(id) {
+                println!("Task {id}: id is used by several tasks, use swap or delete to fix it");
+            } else {
+                seen.push(*id);
+            }
+        }
+
+        if repairs == 0 {
+            println!("No dangling reference found");
+            return;
+        }
+
+        self.save(&tasks);
+    }
+
+    pub fn swap_tasks(&self, id1: &TaskRef, id2: &TaskRef) {
+        let mut tasks = self.load();
+        let Some(index1) = find_task(&tasks, id1, "Task 1") else {
+            return;
+        };
+        let Some(index2) = find_task(&tasks, id2, "Task 2") else {
+            return;
+        };
+        let (id1, id2) = (tasks[index1].id, tasks[index2].id);
+
+        tasks[index1].id = id2;
+        tasks[index2].id = id1;
+
+        let swap = |id| match id {
+            id if id == id1 => id2,
+            id if id == id2 => id1,
+            id => id,
+        };
+
+        for task in &mut tasks {
+            task.parent = task.parent.map(swap);
+            task.depends_on = task.depends_on.iter().copied().map(swap).collect();
+        }
+
+        self.save(&tasks);
+    }
+
+    /// Moves a task to `position` (from 1) among the tasks `list --sort
+    /// manual` shows, shifting the others down. Ids don't change.
+    pub fn move_task(&self, id: &TaskRef, position: usize) {
+        let mut tasks = self.load();
+        let Some(index) = find_task(&tasks, id, "Task") else {
+            return;
+        };
+        let task = tasks.remove(index);
+        let today = today();
+        let target = tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| task.is_open() && !task.is_waiting(today))
+            .nth(position.saturating_sub(1))
+            .map_or(tasks.len(), |(index, _)| index);
+
+        tasks.insert(target, task);
+
+        self.save(&tasks);
+    }
+
+    /// Moves a task, or all closed tasks when `id` is `None`, to the archive
+    /// file.
+    pub fn archive_tasks(&self, id: Option<&TaskRef>) {
+        let tasks = self.load();
+        let ids: Vec<u32> = match id {
+            Some(id) => {
+                let Some(index) = find_task(&tasks, id, "Task") else {
+                    return;
+                };
+
+                vec![tasks[index].id]
+            }
+            None => tasks
+                .iter()
+                .filter(|task| !task.is_open() && !task.ephemeral)
+                .map(|task| task.id)
+                .collect(),
+        };
+
+        if ids.is_empty() {
+            println!("No task to archive");
+            return;
+        }
+
+        let (archived, mut tasks): (Vec<TaskItem>, Vec<TaskItem>) =
+            tasks.into_iter().partition(|task| ids.contains(&task.id));
+        let uuids: Vec<Uuid> = archived.iter().map(|task| task.uuid).collect();
+
+        // The archive is written first: if the store can't be written after
+        // it, archiving again or running doctor removes the tasks left in
+        // both files.
+        if !self.update_archive(|archive| {
+            archive.retain(|task| !uuids.contains(&task.uuid));
+            archive.extend(archived);
+        }) {
+            return;
+        }
+
+        self.archived.borrow_mut().extend(uuids);
+
+        for task in &mut tasks {
+            if task.parent.is_some_and(|parent| ids.contains(&parent)) {
+                task.parent = None;
+            }
+
+            task.depends_on
+                .retain(|dependency| !ids.contains(dependency));
+        }
+
+        println!("Archived {}", pluralize(ids.len(), "task", "tasks"));
+
+        self.save(&tasks);
+    }
+
+    /// Rewrites the store and its archive in the format set in the
+    /// configuration.
+    pub fn migrate(&self) {
+        if self.config.compress && !cfg!(feature = "compress") {
+            eprintln!("{COMPRESS_UNSUPPORTED}");
+            return;
+        }
+
+        let tasks = self.load();
+        let archive_path = self.archive_path();
+
+        if self.unreadable.get() {
+            eprintln!("{} was left unchanged", self.path.display());
+            return;
+        }
+
+        if archive_path.exists() {
+            let Ok(archive) = read_tasks(&archive_path) else {
+                eprintln!("Could not read {}", archive_path.display());
+                return;
+            };
+
+            if let Err(error) = self.write(&archive_path, &archive) {
+                eprintln!("Could not write to {}: {error:#}", archive_path.display());
+                return;
+            }
+        }
+
+        // Written directly, saving skips tasks that didn't change.
+        if let Err(error) = self.write(&self.path, &tasks) {
+            eprintln!("Could not write to {}: {error:#}", self.path.display());
+            return;
+        }
+
+        println!(
+            "Converted {} to {}{}",
+            pluralize(tasks.len(), "task", "tasks"),
+            self.config.format,
+            if self.config.compress {
+                " (compressed)"
+            } else {
+                ""
+            }
+        );
+    }
+
+    /// Opens the pinned notes of the list in `$VISUAL` or `$EDITOR`.
+    pub fn edit_pinned(&self) {
+        let path = self.pinned_path();
+
+        if open_editor(&path)
+            && fs::read_to_string(&path).is_ok_and(|pinned| pinned.trim().is_empty())
+        {
+            let _ = fs::remove_file(&path);
+        }
+    }
+
+    pub fn show_pinned(&self) {
+        if let Some(pinned) = self.pinned() {
+            println!("{pinned}");
+        }
+    }
+
+    pub fn clear_pinned(&self) {
+        match fs::remove_file(self.pinned_path()) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(_) => eprintln!("Could not remove {}", self.pinned_path().display()),
+        }
+    }
+
+    /// Free text kept with the list and shown above it, for what isn't a
+    /// task (door codes, standing links).
+    fn pinned(&self) -> Option<String> {
+        let pinned = fs::read_to_string(self.pinned_path()).ok()?;
+        let pinned = pinned.trim_end();
+
+        (!pinned.is_empty()).then(|| pinned.to_string())
+    }
+
+    fn pinned_path(&self) -> PathBuf {
+        self.path.with_extension("notes.md")
+    }
+
+    /// Applies `update` to the archived tasks, returning `false` when the
+    /// archive could not be read or written.
+    fn update_archive(&self, update: impl FnOnce(&mut Vec<TaskItem>)) -> bool {
+        let archive_path = self.archive_path();
+        let mut archive = if archive_path.exists() {
+            let Ok(archive) = read_tasks(&archive_path) else {
+                eprintln!("Could not read {}", archive_path.display());
+                return false;
+            };
+
+            archive
+        } else {
+            Vec::new()
+        };
+
+        update(&mut archive);
+
+        if let Err(error) = self.write(&archive_path, &archive) {
+            eprintln!("Could not write to {}: {error:#}", archive_path.display());
+            return false;
+        }
+
+        true
+    }
+
+    fn archive_path(&self) -> PathBuf {
+        self.path.with_extension("archive.json")
+    }
+
+    pub fn reset_tasks(&self, force: bool) {
+        let mut tasks = self.load();
+
+        if tasks.is_empty() {
+            return;
+        }
+
+        let truncate = force || {
+            let Some(confirmed) = confirm(&format!(
+                "Are your sure you want to permanently delete {} (y/N)?",
+                pluralize(tasks.len(), "task", "tasks")
+            )) else {
+                return;
+            };
+
+            confirmed
+        };
+
+        if truncate {
+            tasks.truncate(0);
+        }
+
+        self.save(&tasks);
+    }
+
+    pub fn count_tasks(&self) {
+        let count = self.cached("count".to_string(), || {
+            let mut count = 0;
+
+            let complete = self.scan(|task| {
+                if task.is_open() {
+                    count += 1;
+                }
+            });
+
+            if complete {
+                format!("{count}\n")
+            } else {
+                String::new()
+            }
+        });
+
+        print!("{count}");
+    }
+
+    /// Counts tasks by state, for tools that need numbers rather than text.
+    /// Done tasks, the total and the score include the archive. Returns
+    /// `None` when the store or the archive could not be read, after
+    /// reporting it.
+    #[must_use]
+    pub fn summary(&self) -> Option<Summary> {
+        let today = today();
+        let mut summary = Summary {
+            pending: 0,
+            done: 0,
+            overdue: 0,
+            due_today: 0,
+            in_progress: 0,
+            total: 0,
+            score: None,
+        };
+
+        let mut count = |task: &TaskItem, archived: bool| {
+            // Scratch tasks are left out of the statistics.
+            if task.ephemeral {
+                return;
+            }
+
+            summary.total += 1;
+
+            if task.status == Status::Done {
+                summary.done += 1;
+            }
+
+            if task.points > 0 && self.config.points {
+                let score = summary.score.get_or_insert(0);
+
+                if task.status == Status::Done {
+                    *score += task.points;
+                }
+            }
+
+            // Archived tasks are part of the history, not of the work left.
+            if archived {
+                return;
+            }
+
+            if task.is_open() {
+                summary.pending += 1;
+            }
+
+            if task.is_overdue(today) {
+                summary.overdue += 1;
+            }
+
+            if task.is_due_today(today) {
+                summary.due_today += 1;
+            }
+
+            if task.status == Status::InProgress {
+                summary.in_progress += 1;
+            }
+        };
+
+        let complete =
+            self.scan(|task| count(task, false)) && self.scan_archive(|task| count(task, true));
+
+        complete.then_some(summary)
+    }
+
+    fn average_lifetime(&self) -> Option<TimeDelta> {
+        let mut total = TimeDelta::zero();
+        let mut count: i32 = 0;
+
+        let mut add = |task: &TaskItem| {
+            if task.status != Status::Done || task.ephemeral {
+                return;
+            }
+
+            if let (Some(completed_at), Some(created_at)) = (task.completed_at, task.created_at) {
+                total += completed_at - created_at;
+                count = count.saturating_add(1);
+            }
+        };
+
+        let complete = self.scan(&mut add) && self.scan_archive(&mut add);
+
+        (complete && count > 0).then(|| total / count)
+    }
+
+    /// Sums the estimates of pending tasks per project, tasks without a
+    /// project being listed under an empty name.
+    fn estimates(&self) -> Option<BTreeMap<String, u32>> {
+        let mut estimates = BTreeMap::new();
+
+        let complete = self.scan(|task| {
+            if let Some(estimate) = task.estimate.filter(|_| task.is_open()) {
+                *estimates
+                    .entry(task.project.clone().unwrap_or_default())
+                    .or_default() += estimate;
+            }
+        });
+
+        complete.then_some(estimates)
+    }
+
+    /// Counts tasks by state per project and per tag, tasks without a project
+    /// being counted under an empty name. Returns `None` like `summary`.
+    fn breakdowns(&self) -> Option<(BTreeMap<String, Counts>, BTreeMap<String, Counts>)> {
+        let today = today();
+        let mut projects: BTreeMap<String, Counts> = BTreeMap::new();
+        let mut tags: BTreeMap<String, Counts> = BTreeMap::new();
+
+        let mut count = |task: &TaskItem, archived: bool| {
+            if task.ephemeral {
+                return;
+            }
+
+            projects
+                .entry(task.project.clone().unwrap_or_default())
+                .or_default()
+                .add(task, archived, today);
+
+            for tag in &task.tags {
+                tags.entry(tag.clone())
+                    .or_default()
+                    .add(task, archived, today);
+            }
+        };
+
+        let complete =
+            self.scan(|task| count(task, false)) && self.scan_archive(|task| count(task, true));
+
+        complete.then_some((projects, tags))
+    }
+
+    /// The state of the files kept with the tasks, for `infos --full`.
+    /// Returns `None` like `summary`.
+    fn files(&self) -> Option<Files> {
+        let mut tasks = 0;
+        let mut archived = 0;
+        let complete = self.scan(|_| tasks += 1) && self.scan_archive(|_| archived += 1);
+
+        if !complete {
+            return None;
+        }
+
+        let (format, compressed) = fs::read(&self.path)
+            .map(|content| detect_format(&content))
+            .unwrap_or_default();
+        let journal = self.read_journal();
+        let cache = fs::read_to_string(self.path.with_extension("cache"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<OutputCache>(&content).ok());
+        let cache = match (cache, self.stamp()) {
+            (None, _) => CacheState::Missing,
+            (Some(cache), Some(stamp)) if cache.stamp == stamp => CacheState::Fresh {
+                outputs: cache.entries.len(),
+            },
+            (Some(_), _) => CacheState::Stale,
+        };
+
+        Some(Files {
+            format,
+            compressed,
+            tasks,
+            archive_size: fs::metadata(self.archive_path())
+                .map(|metadata| metadata.len())
+                .ok(),
+            archived,
+            undo: journal.undo.len(),
+            redo: journal.redo.len(),
+            cache,
+        })
+    }
+
+    /// Prints statistics on the tasks, and on the files kept with them when
+    /// `full` is set.
+    pub fn infos(&self, json: bool, full: bool) {
+        // The errors were reported, partial numbers would be misleading.
+        let (Some(summary), Some(estimates), Some((projects, tags))) =
+            (self.summary(), self.estimates(), self.breakdowns())
+        else {
+            return;
+        };
+        let files = if full {
+            let Some(files) = self.files() else {
+                return;
+            };
+
+            Some(files)
+        } else {
+            None
+        };
+        let size = fs::metadata(&self.path).map(|metadata| metadata.len()).ok();
+
+        if json {
+            let infos = Infos {
+                path: &self.path,
+                size,
+                summary,
+                estimates,
+                projects,
+                tags,
+                files,
+            };
+
+            match serde_json::to_string(&infos) {
+                Ok(infos) => println!("{infos}"),
+                Err(_) => eprintln!("Could not serialize infos"),
+            }
+
+            return;
+        }
+
+        println!("File location: {}", &self.path.display());
+
+        if let Some(size) = size {
+            println!("File size: {size} bytes");
+        }
+
+        if let Some(files) = &files {
+            files.print();
+        }
+
+        println!("Done tasks: {}", summary.done);
+        println!("Remaining tasks: {}", summary.pending);
+        println!("In progress tasks: {}", summary.in_progress);
+        println!("Overdue tasks: {}", summary.overdue);
+        println!("Tasks due today: {}", summary.due_today);
+        println!("Total tasks: {}", summary.total);
+
+        if let Some(lifetime) = self.average_lifetime() {
+            println!(
+                "Average time to completion: {}",
+                pluralize(
+                    usize::try_from(lifetime.num_days()).unwrap_or_default(),
+                    "day",
+                    "days"
+                )
+            );
+        }
+
+        if let Some(score) = summary.score {
+            println!("Score: {score} (level {})", level(score));
+        }
+
+        if !estimates.is_empty() {
+            println!("Estimated work left:");
+        }
+
+        for (project, estimate) in &estimates {
+            let project = if project.is_empty() {
+                "(no project)"
+            } else {
+                project
+            };
+
+            println!("  {project}: {}", display_estimate(&Some(*estimate)));
+        }
+
+        // Only worth a table when the tasks are actually organized.
+        if projects.keys().any(|project| !project.is_empty()) {
+            println!("\n{}", render_counts("Project", &projects));
+        }
+
+        if !tags.is_empty() {
+            println!("\n{}", render_counts("Tag", &tags));
+        }
+    }
+
+    /// Summarizes the tasks completed, added and overdue over `period`, in
+    /// Markdown or HTML for status emails. The report is written to a dated
+    /// file in `directory` when given, e.g. by a weekly cron job, and
+    /// printed otherwise.
+    pub fn report_summary(&self, period: Period, html: bool, directory: Option<&Path>) {
+        let today = today();
+        let start = period.start(today);
+        let in_period = |moment: Option<DateTime<Utc>>| {
+            moment.is_some_and(|moment| {
+                (start..=today).contains(&moment.with_timezone(&Local).date_naive())
+            })
+        };
+        let mut report = Report {
+            period,
+            start,
+            end: today,
+            completed: Vec::new(),
+            added: 0,
+            overdue: Vec::new(),
+            streak: 0,
+        };
+        let mut completion_days = BTreeSet::new();
+
+        let mut add = |task: &TaskItem, archived: bool| {
+            if task.ephemeral {
+                return;
+            }
+
+            if let Some(completed_at) = task.completed_at.filter(|_| task.status == Status::Done) {
+                let day = completed_at.with_timezone(&Local).date_naive();
+                completion_days.insert(day);
+
+                if in_period(task.completed_at) {
+                    report.completed.push((day, task.task.clone()));
+                }
+            }
+
+            if in_period(task.created_at) {
+                report.added += 1;
+            }
+
+            if let Some(due) = task.due.filter(|_| !archived && task.is_overdue(today)) {
+                report.overdue.push((due, task.task.clone()));
+            }
+        };
+
+        if !(self.scan(|task| add(task, false)) && self.scan_archive(|task| add(task, true))) {
+            return;
+        }
+
+        report.completed.sort();
+        report.overdue.sort();
+        report.streak = streak(&completion_days, today);
+
+        let content = if html {
+            report.html()
+        } else {
+            report.markdown()
+        };
+
+        let Some(directory) = directory else {
+            print!("{content}");
+            return;
+        };
+        let path = directory.join(format!(
+            "summary-{period}-{today}.{}",
+            if html { "html" } else { "md" }
+        ));
+
+        match fs::create_dir_all(directory).and_then(|()| fs::write(&path, content)) {
+            Ok(()) => println!("Wrote {}", path.display()),
+            Err(error) => eprintln!("Could not write to {}: {error}", path.display()),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub pending: usize,
+    pub done: usize,
+    pub overdue: usize,
+    pub due_today: usize,
+    pub in_progress: usize,
+    pub total: usize,
+    /// Points of the done tasks, `None` when no task has points or they are
+    /// turned off in the configuration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct Infos<'a> {
+    path: &'a Path,
+    size: Option<u64>,
+    #[serde(flatten)]
+    summary: Summary,
+    /// Minutes of pending work per project.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    estimates: BTreeMap<String, u32>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    projects: BTreeMap<String, Counts>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    tags: BTreeMap<String, Counts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<Files>,
+}
+
+/// The store, its archive, undo journal and output cache, as `infos --full`
+/// reports them.
+#[derive(Serialize)]
+struct Files {
+    /// `None` without a store, or when it is compressed and taskrs can't
+    /// decompress it.
+    format: Option<StoreFormat>,
+    compressed: bool,
+    tasks: usize,
+    archive_size: Option<u64>,
+    archived: usize,
+    /// Operations that can be undone, and redone.
+    undo: usize,
+    redo: usize,
+    cache: CacheState,
+}
+
+impl Files {
+    fn print(&self) {
+        println!(
+            "File format: {}{}",
+            self.format
+                .map_or_else(|| "unknown".to_string(), |format| format.to_string()),
+            if self.compressed { " (compressed)" } else { "" }
+        );
+        println!("Tasks in the file: {}", self.tasks);
+
+        if let Some(size) = self.archive_size {
+            println!(
+                "Archive: {}, {size} bytes",
+                pluralize(self.archived, "task", "tasks")
+            );
+        }
+
+        println!(
+            "Undo history: {} to undo, {} to redo",
+            pluralize(self.undo, "change", "changes"),
+            self.redo
+        );
+
+        match self.cache {
+            CacheState::Missing => println!("Output cache: none"),
+            CacheState::Stale => println!("Output cache: stale, rebuilt on the next read"),
+            CacheState::Fresh { outputs } => println!(
+                "Output cache: up to date, {}",
+                pluralize(outputs, "output", "outputs")
+            ),
+        }
+    }
+}
+
+/// Whether the cached outputs match the current state of the store.
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase", tag = "state")]
+enum CacheState {
+    Missing,
+    Stale,
+    Fresh { outputs: usize },
+}
+
+/// Tasks of a project or a tag by state. Like in `Summary`, done tasks
+/// include the archive and the others don't.
+#[derive(Serialize, Default)]
+struct Counts {
+    pending: usize,
+    done: usize,
+    overdue: usize,
+}
+
+impl Counts {
+    fn add(&mut self, task: &TaskItem, archived: bool, today: NaiveDate) {
+        if task.status == Status::Done {
+            self.done += 1;
+        }
+
+        if archived {
+            return;
+        }
+
+        if task.is_open() {
+            self.pending += 1;
+        }
+
+        if task.is_overdue(today) {
+            self.overdue += 1;
+        }
+    }
+}
+
+/// What `report summary` tells about a period.
+struct Report {
+    period: Period,
+    start: NaiveDate,
+    end: NaiveDate,
+    /// Day and text of the tasks done in the period.
+    completed: Vec<(NaiveDate, String)>,
+    added: usize,
+    /// Due date and text of the tasks overdue at the end of the period.
+    overdue: Vec<(NaiveDate, String)>,
+    /// Days in a row with a task done, up to the end of the period.
+    streak: usize,
+}
+
+impl Report {
+    fn title(&self) -> String {
+        let period = match self.period {
+            Period::Week => "Weekly",
+            Period::Month => "Monthly",
+        };
+
+        format!("{period} summary, {} to {}", self.start, self.end)
+    }
+
+    fn figures(&self) -> [String; 4] {
+        [
+            format!(
+                "{} completed",
+                pluralize(self.completed.len(), "task", "tasks")
+            ),
+            format!("{} added", pluralize(self.added, "task", "tasks")),
+            format!("{} overdue", pluralize(self.overdue.len(), "task", "tasks")),
+            format!("Streak: {}", pluralize(self.streak, "day", "days")),
+        ]
+    }
+
+    fn sections(&self) -> [(&str, Vec<String>); 2] {
+        [
+            (
+                "Completed",
+                self.completed
+                    .iter()
+                    .map(|(day, task)| format!("{day}: {task}"))
+                    .collect(),
+            ),
+            (
+                "Overdue",
+                self.overdue
+                    .iter()
+                    .map(|(due, task)| format!("{task} (due {due})"))
+                    .collect(),
+            ),
+        ]
+    }
+
+    fn markdown(&self) -> String {
+        let mut output = format!("# {}\n\n", self.title());
+
+        for figure in self.figures() {
+            let _ = writeln!(output, "- {figure}");
+        }
+
+        for (heading, items) in self.sections() {
+            if items.is_empty() {
+                continue;
+            }
+
+            let _ = writeln!(output, "\n## {heading}\n");
+
+            for item in items {
+                let _ = writeln!(output, "- {item}");
+            }
+        }
+
+        output
+    }
+
+    fn html(&self) -> String {
+        let list = |items: &[String]| {
+            let mut list = "<ul>\n".to_string();
+
+            for item in items {
+                let _ = writeln!(list, "<li>{}</li>", escape_html(item));
+            }
+
+            list + "</ul>\n"
+        };
+        let title = escape_html(&self.title());
+        let mut output = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n"
+        );
+
+        output.push_str(&list(&self.figures()));
+
+        for (heading, items) in self.sections() {
+            if !items.is_empty() {
+                let _ = write!(output, "<h2>{heading}</h2>\n{}", list(&items));
+            }
+        }
+
+        output.push_str("</body>\n</html>\n");
+        output
+    }
+}
+
+/// Tasks matching `options`, checked in parallel with the `parallel` feature.
+#[cfg(feature = "parallel")]
+fn filter_tasks<'a>(
+    tasks: &'a [TaskItem],
+    options: &ListOptions,
+    today: NaiveDate,
+) -> Vec<&'a TaskItem> {
+    use rayon::prelude::*;
+
+    tasks
+        .par_iter()
+        .filter(|task| options.matches(task, today))
+        .collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn filter_tasks<'a>(
+    tasks: &'a [TaskItem],
+    options: &ListOptions,
+    today: NaiveDate,
+) -> Vec<&'a TaskItem> {
+    tasks
+        .iter()
+        .filter(|task| options.matches(task, today))
+        .collect()
+}
+
+/// The tasks as tables, one per project, below the focused task if any.
+fn render_tables(tasks: Vec<&TaskItem>, options: &ListOptions) -> String {
+    let mut output = String::new();
+
+    if let Some(focused) = tasks.iter().find(|task| task.focused) {
+        let _ = writeln!(output, "Focus: {} {}\n", focused.id, focused.task);
+    }
+
+    if tasks.iter().any(|task| task.project.is_some()) {
+        let mut groups: BTreeMap<Option<&str>, Vec<&TaskItem>> = BTreeMap::new();
+
+        for task in tasks {
+            groups
+                .entry(task.project.as_deref())
+                .or_default()
+                .push(task);
+        }
+
+        // Tasks without a project come last, after the named projects.
+        let unassigned = groups.remove(&None);
+
+        for (index, (project, tasks)) in groups
+            .into_iter()
+            .chain(unassigned.map(|tasks| (None, tasks)))
+            .enumerate()
+        {
+            if index > 0 {
+                output.push('\n');
+            }
+
+            let _ = writeln!(output, "{}", project.unwrap_or("(no project)"));
+            let _ = writeln!(output, "{}", render_table(&tasks, options));
+        }
+    } else {
+        let _ = writeln!(output, "{}", render_table(&tasks, options));
+    }
+
+    output
+}
+
+/// A table of `counts` by project or tag, as named by `heading`.
+fn render_counts(heading: &str, counts: &BTreeMap<String, Counts>) -> Table {
+    let mut builder = Builder::new();
+    builder.push_record([heading, "Pending", "Done", "Overdue"]);
+
+    // Tasks without a project come last, after the named projects.
+    for (name, counts) in counts
+        .iter()
+        .filter(|(name, _)| !name.is_empty())
+        .chain(counts.get_key_value(""))
+    {
+        builder.push_record([
+            if name.is_empty() {
+                "(no project)".to_string()
+            } else {
+                name.clone()
+            },
+            counts.pending.to_string(),
+            counts.done.to_string(),
+            counts.overdue.to_string(),
+        ]);
+    }
+
+    let mut table = builder.build();
+    table.with(Style::blank());
+    table
+}
+
+/// The tasks as a numbered plain list grouped by their first tag, to read
+/// on a phone or print.
+fn render_checklist(tasks: &[&TaskItem]) -> String {
+    let mut groups: BTreeMap<Option<&str>, Vec<&TaskItem>> = BTreeMap::new();
+
+    for task in tasks {
+        groups
+            .entry(task.tags.first().map(String::as_str))
+            .or_default()
+            .push(task);
+    }
+
+    // Untagged tasks come last, after the tags.
+    let untagged = groups.remove(&None);
+    let mut output = String::new();
+    let mut number = 0;
+
+    for (index, (tag, tasks)) in groups
+        .into_iter()
+        .chain(untagged.map(|tasks| (None, tasks)))
+        .enumerate()
+    {
+        if index > 0 {
+            output.push('\n');
+        }
+
+        let _ = writeln!(
+            output,
+            "{}",
+            tag.map_or_else(|| "Other".to_string(), |tag| format!("#{tag}"))
+        );
+
+        for task in tasks {
+            number += 1;
+            let _ = writeln!(
+                output,
+                "{number:>2}. {} {}",
+                display_status(&task.status),
+                task.task
+            );
+        }
+    }
+
+    output
+}
+
+fn render_table(tasks: &[&TaskItem], options: &ListOptions) -> Table {
+    let rows: Vec<TaskItem> = if options.tree {
+        tree_order(tasks)
+            .into_iter()
+            .map(|(depth, task)| {
+                let mut row = task.clone();
+
+                if depth > 0 {
+                    row.task = format!("{}└ {}", "  ".repeat(depth - 1), row.task);
+                }
+
+                row
+            })
+            .collect()
+    } else {
+        tasks.iter().map(|task| (*task).clone()).collect()
+    };
+
+    let mut table = Table::new(rows);
+    table.with(Style::blank());
+
+    if !options.timestamps {
+        let columns = table.count_columns();
+        table.with(Disable::column(Columns::new(columns - 2..columns)));
+    }
+
+    if let Some(width) = options.width {
+        table.with(Width::truncate(width).suffix("…").priority::<PriorityMax>());
+    }
+
+    table
+}
+
+/// Prints one JSON object on stderr for each task added, updated or removed
+/// between `previous` and `tasks`, updates listing the fields that changed.
+fn emit_events(operation: Uuid, previous: &[TaskItem], tasks: &[TaskItem]) {
+    let to_value = |task: &TaskItem| serde_json::to_value(task).unwrap_or_default();
+
+    for task in tasks {
+        let event = match previous.iter().find(|before| before.uuid == task.uuid) {
+            None => json!({
+                "event": "added",
+                "operation": operation,
+                "id": task.id,
+                "uuid": task.uuid,
+                "task": task,
+            }),
+            Some(before) => {
+                let (before, after) = (to_value(before), to_value(task));
+                let (Some(before), Some(after)) = (before.as_object(), after.as_object()) else {
+                    continue;
+                };
+                let changes: BTreeSet<&String> = before
+                    .keys()
+                    .chain(after.keys())
+                    .filter(|key| before.get(*key) != after.get(*key))
+                    .collect();
+
+                if changes.is_empty() {
+                    continue;
+                }
+
+                json!({
+                    "event": "updated",
+                    "operation": operation,
+                    "id": task.id,
+                    "uuid": task.uuid,
+                    "changes": changes,
+                    "task": task,
+                })
+            }
+        };
+
+        eprintln!("{event}");
+    }
+
+    for task in previous
+        .iter()
+        .filter(|before| !tasks.iter().any(|task| task.uuid == before.uuid))
+    {
+        eprintln!(
+            "{}",
+            json!({
+                "event": "removed",
+                "operation": operation,
+                "id": task.id,
+                "uuid": task.uuid,
+                "task": task,
+            })
+        );
+    }
+}
+
+/// Tasks added, changed or removed between `previous` and `tasks`.
+fn diff_tasks(previous: &[TaskItem], tasks: &[TaskItem]) -> Vec<Change> {
+    let to_value = |task: &TaskItem| serde_json::to_value(task).ok();
+    let mut changes: Vec<Change> = tasks
+        .iter()
+        .filter_map(
+            |task| match previous.iter().find(|before| before.uuid == task.uuid) {
+                Some(before) if to_value(before) == to_value(task) => None,
+                before => Some(Change {
+                    before: before.cloned(),
+                    after: Some(task.clone()),
+                }),
+            },
+        )
+        .collect();
+
+    changes.extend(
+        previous
+            .iter()
+            .filter(|before| !tasks.iter().any(|task| task.uuid == before.uuid))
+            .map(|before| Change {
+                before: Some(before.clone()),
+                after: None,
+            }),
+    );
+
+    changes
+}
+
+/// The order of `previous` and `tasks` when the tasks found in both are not
+/// in the same order, e.g. after a move.
+fn reorder(previous: &[TaskItem], tasks: &[TaskItem]) -> Option<Reorder> {
+    let uuids = |tasks: &[TaskItem]| -> Vec<Uuid> { tasks.iter().map(|task| task.uuid).collect() };
+    let (before, after) = (uuids(previous), uuids(tasks));
+    let kept = |from: &[Uuid], other: &[Uuid]| {
+        let other: BTreeSet<&Uuid> = other.iter().collect();
+
+        from.iter()
+            .filter(|uuid| other.contains(uuid))
+            .copied()
+            .collect::<Vec<_>>()
+    };
+
+    (kept(&before, &after) != kept(&after, &before)).then_some(Reorder { before, after })
+}
+
+/// Returns the index of the task `reference` points to, reporting missing or
+/// ambiguous references as `what`.
+fn find_task(tasks: &[TaskItem], reference: &TaskRef, what: &str) -> Option<usize> {
+    let mut matches = tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, task)| reference.matches(task))
+        .map(|(index, _)| index);
+
+    match (matches.next(), matches.next()) {
+        (Some(index), None) => Some(index),
+        (Some(_), Some(_)) => {
+            eprintln!("{what} `{reference}` is ambiguous, use more characters of its UUID");
+            None
+        }
+        (None, _) => {
+            eprintln!("{what} `{reference}` not found");
+            None
+        }
+    }
+}
+
+/// Ids of the tasks picked by `selectors`, without duplicates. Text
+/// selectors only pick `candidate` tasks.
+fn select_tasks(
+    tasks: &[TaskItem],
+    selectors: &[Selector],
+    candidate: impl Fn(&TaskItem) -> bool,
+) -> Vec<u32> {
+    let mut ids = Vec::new();
+
+    for selector in selectors {
+        let found: Vec<u32> = match selector {
+            Selector::Task(reference) => find_task(tasks, reference, "Task")
+                .map(|index| tasks[index].id)
+                .into_iter()
+                .collect(),
+            Selector::Range(start, end) => tasks
+                .iter()
+                .map(|task| task.id)
+                .filter(|id| (start..=end).contains(&id))
+                .collect(),
+            Selector::Matching(text) => {
+                let needle = text.to_lowercase();
+                let found: Vec<u32> = tasks
+                    .iter()
+                    .filter(|task| candidate(task) && task.task.to_lowercase().contains(&needle))
+                    .map(|task| task.id)
+                    .collect();
+
+                if found.is_empty() {
+                    eprintln!("No task matches \"{text}\"");
+                }
+
+                found
+            }
+        };
+
+        for id in found {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    ids.sort_unstable();
+    ids
+}
+
+/// Days in a row, among `days`, up to today. A streak is still running when
+/// the last day is yesterday, the day isn't over yet.
+fn streak(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> usize {
+    let mut day = if days.contains(&today) {
+        Some(today)
+    } else {
+        today.pred_opt()
+    };
+    let mut streak = 0;
+
+    while let Some(current) = day.filter(|day| days.contains(day)) {
+        streak += 1;
+        day = current.pred_opt();
+    }
+
+    streak
+}
+
+/// Ids of all the tasks `id` depends on, directly or not.
+fn dependencies(tasks: &[TaskItem], id: u32) -> Vec<u32> {
+    let mut found = Vec::new();
+    let mut queue = vec![id];
+
+    while let Some(current) = queue.pop() {
+        let Some(task) = tasks.iter().find(|task| task.id == current) else {
+            continue;
+        };
+
+        for dependency in &task.depends_on {
+            if !found.contains(dependency) {
+                found.push(*dependency);
+                queue.push(*dependency);
+            }
+        }
+    }
+
+    found
+}
+
+fn mark_blocked(tasks: &mut [TaskItem]) {
+    let pending: Vec<u32> = tasks
+        .iter()
+        .filter(|task| task.is_open())
+        .map(|task| task.id)
+        .collect();
+
+    for task in tasks {
+        task.blocked = task.depends_on.iter().any(|id| pending.contains(id));
+    }
+}
+
+/// Ids of all the tasks below `id` in the hierarchy.
+fn descendants(tasks: &[TaskItem], id: u32) -> Vec<u32> {
+    let mut found = Vec::new();
+    let mut queue = vec![id];
+
+    while let Some(parent) = queue.pop() {
+        for task in tasks.iter().filter(|task| task.parent == Some(parent)) {
+            if task.id != id && !found.contains(&task.id) {
+                found.push(task.id);
+                queue.push(task.id);
+            }
+        }
+    }
+
+    found
+}
+
+/// Orders tasks depth-first so children follow their parent, paired with
+/// their depth. Tasks whose parent is not in `tasks` are treated as roots.
+fn tree_order<'a>(tasks: &[&'a TaskItem]) -> Vec<(usize, &'a TaskItem)> {
+    let ids: Vec<u32> = tasks.iter().map(|task| task.id).collect();
+    let mut ordered = Vec::with_capacity(tasks.len());
+    let mut stack: Vec<(usize, &TaskItem)> = tasks
+        .iter()
+        .rev()
+        .filter(|task| task.parent.is_none_or(|parent| !ids.contains(&parent)))
+        .map(|task| (0, *task))
+        .collect();
+
+    while let Some((depth, task)) = stack.pop() {
+        if ordered
+            .iter()
+            .any(|(_, current): &(usize, &TaskItem)| current.id == task.id)
+        {
+            continue;
+        }
+
+        ordered.push((depth, task));
+        stack.extend(
+            tasks
+                .iter()
+                .rev()
+                .filter(|child| child.parent == Some(task.id))
+                .map(|child| (depth + 1, *child)),
+        );
+    }
+
+    ordered
+}
+
+/// Checks `task` against the length and line limits of `config`.
+fn validate_task(task: &str, config: &Config) -> Result<(), String> {
+    let length = task.chars().count();
+    let max_length = config.max_task_length.unwrap_or(MAX_TASK_LENGTH);
+
+    if length > max_length {
+        return Err(format!(
+            "The task is {length} characters long (limit is {max_length})"
+        ));
+    }
+
+    let lines = task.lines().count();
+    let max_lines = config.max_task_lines.unwrap_or(MAX_TASK_LINES);
+
+    if lines > max_lines {
+        return Err(format!(
+            "The task spans {lines} lines (limit is {max_lines})"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Collapses whitespace, drops trailing punctuation and capitalizes the
+/// first letter.
+fn normalize_task(task: &str) -> String {
+    let task = task.split_whitespace().collect::<Vec<_>>().join(" ");
+    let task = task.trim_end_matches(['.', ',', ';', ':']);
+    let mut chars = task.chars();
+
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// Tags already used in the store that appear as words of `task` but are not
+/// attached to it.
+fn suggest_tags(tasks: &[TaskItem], task: &TaskItem) -> Vec<String> {
+    let words: Vec<String> = task
+        .task
+        .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+        .map(str::to_lowercase)
+        .collect();
+    let mut suggestions: Vec<String> = tasks
+        .iter()
+        .flat_map(|task| &task.tags)
+        .filter(|tag| words.contains(tag) && !task.has_tag(tag))
+        .cloned()
+        .collect();
+
+    suggestions.sort();
+    suggestions.dedup();
+    suggestions
+}
+
+fn short_title(text: &str) -> String {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+
+    if line.chars().count() > SHORT_TITLE_LENGTH {
+        let title: String = line.chars().take(SHORT_TITLE_LENGTH - 1).collect();
+
+        format!("{}…", title.trim_end())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Creates a file only the current user can read, failing if `path` already
+/// exists, even as a dangling symlink.
+fn create_private(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options.open(path)
+}
+
+/// Opens `path` in `$VISUAL` or `$EDITOR`, falling back to `vi`, and
+/// returns whether the editor exited successfully.
+fn open_editor(path: &Path) -> bool {
+    let editor = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let Some((program, args)) = shlex::split(&editor)
+        .filter(|words| !words.is_empty())
+        .map(|mut words| (words.remove(0), words))
+    else {
+        eprintln!("Invalid editor command: {editor}");
+        return false;
+    };
+
+    let status = process::Command::new(&program)
+        .args(args)
+        .arg(path)
+        .status();
+
+    if !status.is_ok_and(|status| status.success()) {
+        eprintln!("Editor {program} failed");
+        return false;
+    }
+
+    true
+}
+
+fn confirm(question: &str) -> Option<bool> {
+    prompt(question).map(|answer| answer == "y")
+}
+
+fn prompt(question: &str) -> Option<String> {
+    println!("{question}");
+
+    let mut input = String::new();
+
+    if io::stdin().read_line(&mut input).is_err() {
+        eprintln!("Could not read user input");
+        return None;
+    }
+
+    Some(input.trim().to_lowercase())
+}
+
+/// Reads tasks in any format, compressed or not, telling them apart by their
+/// header.
+fn read_tasks<P: AsRef<Path>>(path: P) -> Result<Vec<TaskItem>> {
+    let mut content = fs::read(path)?;
+
+    // Like a missing store, e.g. created with `touch`.
+    if content.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if content.starts_with(&ZSTD_MAGIC) {
+        content = decompress(&content)?;
+    }
+
+    let mut tasks: Vec<TaskItem> = match content.strip_prefix(BINARY_MAGIC) {
+        Some([BINARY_VERSION, data @ ..]) => rmp_serde::from_slice(data)?,
+        Some(_) => bail!("Unsupported binary store version"),
+        None => serde_json::from_slice(&content)?,
+    };
+
+    tasks.iter_mut().for_each(TaskItem::upgrade);
+
+    Ok(tasks)
+}
+
+/// Calls `f` with each task of the file at `path` in turn, without holding
+/// them all in memory.
+fn scan_tasks<P: AsRef<Path>>(path: P, f: impl FnMut(TaskItem)) -> Result<()> {
+    struct Scanner<F>(F);
+
+    impl<'de, F: FnMut(TaskItem)> Visitor<'de> for Scanner<F> {
+        type Value = ();
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a list of tasks")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+            while let Some(mut task) = seq.next_element::<TaskItem>()? {
+                task.upgrade();
+                (self.0)(task);
+            }
+
+            Ok(())
+        }
+    }
+
+    let mut reader = BufReader::new(File::open(&path)?);
+
+    // Binary and compressed stores are compact enough to be read at once.
+    let header = reader.fill_buf()?;
+
+    if header.is_empty() {
+        return Ok(());
+    }
+
+    if header.starts_with(BINARY_MAGIC) || header.starts_with(&ZSTD_MAGIC) {
+        read_tasks(path)?.into_iter().for_each(f);
+        return Ok(());
+    }
+
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+
+    deserializer.deserialize_seq(Scanner(f))?;
+
+    Ok(())
+}
+
+fn encode_tasks(tasks: &[TaskItem], config: &Config) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+
+    match config.format {
+        StoreFormat::Json => serde_json::to_writer(&mut content, &tasks)?,
+        StoreFormat::Binary => {
+            content.extend_from_slice(BINARY_MAGIC);
+            content.push(BINARY_VERSION);
+            rmp_serde::encode::write_named(&mut content, &tasks)?;
+        }
+    }
+
+    if config.compress {
+        content = compress(&content)?;
+    }
+
+    Ok(content)
+}
+
+#[cfg(feature = "compress")]
+fn compress(content: &[u8]) -> Result<Vec<u8>> {
+    Ok(zstd::encode_all(content, 0)?)
+}
+
+#[cfg(not(feature = "compress"))]
+fn compress(_content: &[u8]) -> Result<Vec<u8>> {
+    bail!(COMPRESS_UNSUPPORTED)
+}
+
+#[cfg(feature = "compress")]
+fn decompress(content: &[u8]) -> Result<Vec<u8>> {
+    Ok(zstd::decode_all(content)?)
+}
+
+#[cfg(not(feature = "compress"))]
+fn decompress(_content: &[u8]) -> Result<Vec<u8>> {
+    bail!(COMPRESS_UNSUPPORTED)
+}
+
+/// The format of a store from its content, and whether it is compressed.
+fn detect_format(content: &[u8]) -> (Option<StoreFormat>, bool) {
+    if content.is_empty() {
+        return (None, false);
+    }
+
+    if !content.starts_with(&ZSTD_MAGIC) {
+        return (Some(detect_plain_format(content)), false);
+    }
+
+    (
+        decompress(content)
+            .ok()
+            .map(|content| detect_plain_format(&content)),
+        true,
+    )
+}
+
+fn detect_plain_format(content: &[u8]) -> StoreFormat {
+    if content.starts_with(BINARY_MAGIC) {
+        StoreFormat::Binary
+    } else {
+        StoreFormat::Json
+    }
+}
+
+/// Whether `error` comes from a file that does not exist.
+fn is_missing(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<io::Error>()
+        .is_some_and(|error| error.kind() == io::ErrorKind::NotFound)
+}
+
+/// A new name for a file written before being renamed over `path`.
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", Uuid::new_v4().simple()));
+
+    path.with_file_name(name)
+}
+
+/// Temporary files of `path` left by interrupted writes.
+fn temporary_files(path: &Path) -> Vec<PathBuf> {
+    let prefix = format!(
+        "{}.",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    );
+    let Ok(entries) = fs::read_dir(directory_of(path)) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|file| {
+            file.extension().is_some_and(|extension| extension == "tmp")
+                && file
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(&prefix))
+        })
+        .collect()
+}
+
+/// Flushes the entries of the directory of `path`, such as a rename, to
+/// disk. Directories cannot be opened for this on Windows.
+fn sync_directory(path: &Path) -> io::Result<()> {
+    if cfg!(unix) {
+        File::open(directory_of(path))?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+fn directory_of(path: &Path) -> &Path {
+    path.parent()
+        .filter(|directory| !directory.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+}
+
+fn display_checklist(checklist: &[ChecklistItem]) -> String {
+    if checklist.is_empty() {
+        return String::new();
+    }
+
+    let done = checklist.iter().filter(|item| item.done).count();
+
+    format!("{done}/{}", checklist.len())
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn display_status(status: &Status) -> String {
+    let checkbox = match status {
+        Status::Todo => "☐",
+        Status::InProgress => "◐",
+        Status::Waiting => "⏸",
+        Status::Done => "🗹",
+        Status::Cancelled => "☒",
+    };
+
+    checkbox.to_string()
+}
+
+#[allow(clippy::ref_option, clippy::trivially_copy_pass_by_ref)]
+fn display_priority(priority: &Option<Priority>) -> String {
+    let flag = match priority {
+        Some(Priority::High) => "!!!",
+        Some(Priority::Medium) => "!!",
+        Some(Priority::Low) => "!",
+        None => "",
+    };
+
+    flag.to_string()
+}
+
+#[allow(clippy::ref_option)]
+fn display_notes(notes: &Option<String>) -> String {
+    let marker = if notes.is_some() { "✎" } else { "" };
+
+    marker.to_string()
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn display_blocked(blocked: &bool) -> String {
+    let marker = if *blocked { "⛓" } else { "" };
+
+    marker.to_string()
+}
+
+fn display_tags(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| format!("#{tag}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn display_duration(duration: TimeDelta) -> String {
+    let minutes = duration.num_minutes();
+
+    if minutes < 60 {
+        format!("{minutes}m")
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+#[allow(clippy::ref_option)]
+fn display_url(url: &Option<String>) -> String {
+    let link = if url.is_some() { "🔗" } else { "" };
+
+    link.to_string()
+}
+
+#[allow(clippy::ref_option)]
+fn display_assignee(assignee: &Option<String>) -> String {
+    assignee
+        .as_ref()
+        .map_or_else(String::new, |assignee| format!("@{assignee}"))
+}
+
+#[allow(clippy::ref_option, clippy::trivially_copy_pass_by_ref)]
+fn display_estimate(estimate: &Option<u32>) -> String {
+    estimate.map_or_else(String::new, |minutes| {
+        display_duration(TimeDelta::minutes(i64::from(minutes)))
+    })
+}
+
+#[allow(clippy::ref_option)]
+fn display_timestamp(timestamp: &Option<DateTime<Utc>>) -> String {
+    timestamp.map_or_else(String::new, |timestamp| {
+        timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    })
+}
+
+fn display_due(task: &TaskItem) -> String {
+    task.due.map_or_else(String::new, |due| {
+        if task.is_overdue(today()) {
+            format!("⚠ {due}")
+        } else {
+            due.to_string()
+        }
+    })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+/// The level reached with `score` points, from 1.
+const fn level(score: u32) -> u32 {
+    score / POINTS_PER_LEVEL + 1
+}
+
+const fn percentage(value: usize, total: usize) -> usize {
+    match (value * 100).checked_div(total) {
+        Some(percentage) => percentage,
+        None => 0,
+    }
 }
 
 fn pluralize(value: usize, singular: &str, plural: &str) -> String {
@@ -1,77 +1,329 @@
-use std::path::PathBuf;
+use std::{
+    io::{self, IsTerminal, Read, Write},
+    process::{self, Stdio},
+};
 
-use clap::{Parser, Subcommand};
+use clap::Parser;
+use cli::{
+    BatchCommand, CheckCommands, Cli, Commands, EventFormat, Format, MetaCommands,
+    NoteListCommands, RemindCommands, ReportCommands, ReportFormat, RulesCommands, Shell,
+    TagCommands,
+};
 use home::home_dir;
-use taskrs::TaskStore;
-
-#[derive(Parser)]
-#[command(about = "A simple command line to-do manager")]
-struct Cli {
-    #[command(subcommand)]
-    command: Option<Commands>,
-
-    #[arg(
-        short,
-        long,
-        help = "The path where to find and store the tasks.json file"
-    )]
-    path: Option<PathBuf>,
+use options::terminal_width;
+use serde_json::json;
+use taskrs::{Config, ListOptions, Status, TaskStore};
+
+mod cli;
+mod options;
+
+fn main() {
+    let cli = Cli::parse();
+    let directory = cli
+        .path
+        .unwrap_or_else(|| home_dir().expect("Could not determine user's home directory"));
+    let config = Config::load(directory.join("config.toml")).unwrap_or_else(|error| {
+        eprintln!("{error:#}");
+        Config::default()
+    });
+    let mut store = TaskStore::new(directory.join("tasks.json"), config);
+
+    if matches!(cli.events, Some(EventFormat::Json)) {
+        store = store.with_events();
+    }
+
+    if cli.simulate_crash {
+        store = store.with_simulated_crash();
+    }
+
+    let Some(command) = cli.command else {
+        return;
+    };
+
+    // Count and focus feed prompts and status bars and the others don't show
+    // tasks, reading the whole store to warn or age the tasks would only slow
+    // them down.
+    let light = matches!(
+        command,
+        Commands::Count | Commands::Focus { id: None } | Commands::Man | Commands::ShellInit { .. }
+    );
+    // The warning is also kept out of stderr when scripts read it, e.g. for
+    // events.
+    let quiet = light || cli.events.is_some() || !io::stderr().is_terminal();
+
+    // Undone aging changes would only be applied again.
+    if !light && !matches!(command, Commands::Undo | Commands::Redo) {
+        store.age_tasks();
+    }
+
+    if !cli.no_nag && !quiet {
+        store.nag();
+    }
+
+    match cli.idempotency_key {
+        Some(key) => store.idempotent(&key, |store| run(store, command)),
+        None => run(&store, command),
+    }
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    #[command(about = "Add a task")]
-    Add { task: String },
+fn run(store: &TaskStore, command: Commands) {
+    match command {
+        Commands::Add(args) => {
+            let (task, options) = args.into_task();
+            store.add_task(task, &options);
+        }
+        Commands::List(args) => store.list_tasks(&args.into()),
+        Commands::Search { query, regex, all } => store.search_tasks(
+            &query,
+            regex,
+            &ListOptions {
+                all,
+                width: terminal_width(),
+                ..ListOptions::default()
+            },
+        ),
+        Commands::Projects => store.list_projects(),
+        Commands::Random { filter } => store.random_task(filter.as_deref()),
+        Commands::Suggest(args) => store.suggest_tasks(args.time, args.count, &args.into()),
+        #[cfg(feature = "tui")]
+        Commands::Tui => store.tui(),
+        Commands::Update { id, task } => store.update_task(&id, task),
+        Commands::Edit { id } => store.edit_task(&id),
+        Commands::NoteList { command } => run_note_list(store, &command),
+        Commands::Note { id, notes, append } => store.note_task(&id, &notes, append),
+        Commands::Show { id } => store.show_task(&id),
+        Commands::Open { id } => store.open_task(&id),
+        Commands::Done {
+            text: Some(text),
+            recursive,
+            ..
+        } => store.mark_matching(&text, Status::Done, recursive),
+        Commands::Done { ids, recursive, .. } => store.mark_tasks(&ids, Status::Done, recursive),
+        Commands::Undone { tasks, recursive } => {
+            store.mark_tasks(&tasks.into_selectors(), Status::Todo, recursive);
+        }
+        Commands::Status {
+            id,
+            status,
+            recursive,
+        } => store.mark_task(&id, status, recursive),
+        Commands::Triage => store.triage(),
+        Commands::Priority { id, level } => store.prioritize_task(&id, level),
+        Commands::Assign { id, name } => store.assign_task(&id, name),
+        Commands::Estimate { id, estimate } => store.estimate_task(&id, estimate),
+        Commands::Tag { command } => run_tag(store, command),
+        Commands::Check { command } => run_check(store, command),
+        Commands::Meta { command } => run_meta(store, command),
+        Commands::Rules {
+            command: RulesCommands::Test { task },
+        } => store.test_rules(&task),
+        Commands::Focus { id: Some(id) } => store.focus_task(&id),
+        Commands::Focus { id: None } => store.print_focus(),
+        Commands::Defer { id, until } => store.defer_task(&id, until),
+        Commands::Remind { command } => run_remind(store, command),
+        Commands::Start { id } => store.start_task(&id),
+        Commands::Stop => store.stop_task(),
+        Commands::Times { id } => store.task_times(&id),
+        Commands::Block { id, on } => store.block_task(&id, &on),
+        Commands::Unblock { id, on } => store.unblock_task(&id, on.as_ref()),
+        Commands::Delete { tasks, mode } => store.delete_tasks(&tasks.into_selectors(), mode),
+        Commands::Archive { id, .. } => store.archive_tasks(id.as_ref()),
+        Commands::Undo => store.undo(),
+        Commands::Redo => store.redo(),
+        Commands::Move { id, position } => store.move_task(&id, position),
+        Commands::Swap { id1, id2 } => store.swap_tasks(&id1, &id2),
+        Commands::Reset { force } => store.reset_tasks(force),
+        Commands::Migrate => store.migrate(),
+        Commands::Doctor => store.doctor(),
+        Commands::Infos { format, full } => store.infos(format == Format::Json, full),
+        Commands::Report {
+            command:
+                ReportCommands::Summary {
+                    period,
+                    format,
+                    output,
+                },
+        } => store.report_summary(period, format == ReportFormat::Html, output.as_deref()),
+        Commands::Count => store.count_tasks(),
+        Commands::ShellInit { shell } => print!(
+            "{}",
+            match shell {
+                Shell::Bash => include_str!("shell/taskrs.bash"),
+                Shell::Zsh => include_str!("shell/taskrs.zsh"),
+                Shell::Fish => include_str!("shell/taskrs.fish"),
+            }
+        ),
+        Commands::Man => print!("{}", include_str!(concat!(env!("OUT_DIR"), "/taskrs.1"))),
+        Commands::Batch => run_batch(store),
+        Commands::Plugin(args) => run_plugin(store, &args),
+    }
+}
+
+fn run_note_list(store: &TaskStore, command: &NoteListCommands) {
+    match command {
+        NoteListCommands::Edit => store.edit_pinned(),
+        NoteListCommands::Show => store.show_pinned(),
+        NoteListCommands::Clear => store.clear_pinned(),
+    }
+}
 
-    #[command(about = "List tasks")]
-    List {
-        #[arg(short, long, help = "Include done tasks")]
-        all: bool,
-    },
+fn run_tag(store: &TaskStore, command: TagCommands) {
+    match command {
+        TagCommands::Add {
+            tasks,
+            tags,
+            no_propagate,
+        } => store.tag_tasks(&tasks.into_selectors(), &tags, !no_propagate),
+        TagCommands::Rm {
+            tasks,
+            tags,
+            no_propagate,
+        } => store.untag_tasks(&tasks.into_selectors(), &tags, !no_propagate),
+    }
+}
 
-    #[command(about = "Update a task")]
-    Update { id: u32, task: String },
+fn run_remind(store: &TaskStore, command: Option<RemindCommands>) {
+    match command {
+        None => store.remind(),
+        Some(RemindCommands::Add {
+            id,
+            when: Some(when),
+            ..
+        }) => store.add_reminder(&id, when),
+        Some(RemindCommands::Add {
+            id,
+            before_due: Some(minutes),
+            ..
+        }) => store.remind_before_due(&id, minutes),
+        Some(RemindCommands::Add { .. }) => {}
+        Some(RemindCommands::Clear { id }) => store.clear_reminders(&id),
+    }
+}
 
-    #[command(about = "Mark a task as done")]
-    Done { id: u32 },
+fn run_check(store: &TaskStore, command: CheckCommands) {
+    match command {
+        CheckCommands::Add { id, item } => store.add_check(&id, &item),
+        CheckCommands::Done { id, number } => store.mark_check(&id, number, true),
+        CheckCommands::Undone { id, number } => store.mark_check(&id, number, false),
+    }
+}
 
-    #[command(about = "Mark a task as undone")]
-    Undone { id: u32 },
+fn run_meta(store: &TaskStore, command: MetaCommands) {
+    match command {
+        MetaCommands::Set { id, key, value } => store.set_meta(&id, &key, &value),
+        MetaCommands::Get { id, key } => store.get_meta(&id, key.as_deref()),
+        MetaCommands::Del { id, key } => store.delete_meta(&id, &key),
+    }
+}
 
-    #[command(about = "Delete a task")]
-    Delete { id: u32 },
+fn run_plugin(store: &TaskStore, args: &[String]) {
+    let Some((name, args)) = args.split_first() else {
+        return;
+    };
+    let program = format!("taskrs-{name}");
+    let context = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "store": store.path(),
+        "args": args,
+    });
 
-    #[command(about = "Swap tasks")]
-    Swap { id1: u32, id2: u32 },
+    let mut child = match process::Command::new(&program)
+        .args(args)
+        .env("TASKRS_STORE", store.path())
+        .stdin(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            eprintln!("Unknown command `{name}` (no `{program}` found on PATH)");
+            process::exit(2);
+        }
+        Err(_) => {
+            eprintln!("Could not run {program}");
+            process::exit(1);
+        }
+    };
 
-    #[command(about = "Empty the task list")]
-    Reset {
-        #[arg(short, long, help = "Don't prompt for confirmation")]
-        force: bool,
-    },
+    if let Some(mut stdin) = child.stdin.take() {
+        // The plugin may exit without reading its context, which is fine.
+        let _ = writeln!(stdin, "{context}");
+    }
 
-    #[command(about = "Get information about your tasks")]
-    Infos,
+    match child.wait() {
+        Ok(status) if status.success() => {}
+        Ok(status) => process::exit(status.code().unwrap_or(1)),
+        Err(_) => {
+            eprintln!("Could not wait for {program}");
+            process::exit(1);
+        }
+    }
 }
 
-fn main() {
-    let cli = Cli::parse();
-    let file_path = cli
-        .path
-        .unwrap_or_else(|| home_dir().expect("Could not determine user's home directory"))
-        .join("tasks.json");
-    let store = TaskStore::new(file_path);
-
-    match cli.command {
-        Some(Commands::Add { task }) => store.add_task(task),
-        Some(Commands::List { all }) => store.list_tasks(all),
-        Some(Commands::Update { id, task }) => store.update_task(id, task),
-        Some(Commands::Done { id }) => store.mark_task(id, true),
-        Some(Commands::Undone { id }) => store.mark_task(id, false),
-        Some(Commands::Delete { id }) => store.delete_task(id),
-        Some(Commands::Swap { id1, id2 }) => store.swap_tasks(id1, id2),
-        Some(Commands::Reset { force }) => store.reset_tasks(force),
-        Some(Commands::Infos) => store.infos(),
-        None => {}
+fn run_batch(store: &TaskStore) {
+    let mut input = String::new();
+
+    if io::stdin().read_to_string(&mut input).is_err() {
+        eprintln!("Could not read commands from stdin");
+        return;
     }
+
+    let commands = match parse_batch(&input) {
+        Ok(commands) => commands,
+        Err(error) => {
+            eprintln!("{error}");
+            eprintln!("No command was applied");
+            return;
+        }
+    };
+    let count = commands.len();
+
+    store.transaction(|store| {
+        for command in commands {
+            run(store, command);
+        }
+    });
+
+    println!("Applied {count} commands");
+}
+
+fn parse_batch(input: &str) -> Result<Vec<Commands>, String> {
+    let lines: Vec<Vec<String>> = if input.trim_start().starts_with('[') {
+        serde_json::from_str(input).map_err(|error| format!("Invalid JSON input: {error}"))?
+    } else {
+        input
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| shlex::split(line).ok_or_else(|| format!("Invalid quoting: {line}")))
+            .collect::<Result<_, _>>()?
+    };
+
+    lines
+        .into_iter()
+        .map(|args| {
+            let command = BatchCommand::try_parse_from(&args)
+                .map_err(|error| format!("Invalid command `{}`:\n{error}", args.join(" ")))?
+                .command;
+
+            match command {
+                Commands::Batch => return Err("A batch cannot contain another batch".to_string()),
+                Commands::Undo | Commands::Redo => {
+                    return Err("Undo and redo cannot run in a batch".to_string())
+                }
+                #[cfg(feature = "tui")]
+                Commands::Tui => return Err("The interface cannot run in a batch".to_string()),
+                Commands::Edit { .. }
+                | Commands::NoteList {
+                    command: NoteListCommands::Edit,
+                } => return Err(format!("Editing is interactive: {}", args.join(" "))),
+                Commands::Triage => return Err("Triage is interactive".to_string()),
+                Commands::Plugin(_) => {
+                    return Err(format!("Plugins cannot run in a batch: {}", args.join(" ")))
+                }
+                _ => {}
+            }
+
+            Ok(command)
+        })
+        .collect()
 }
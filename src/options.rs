@@ -0,0 +1,99 @@
+use taskrs::{page_title, parse_quick, ListOptions, Status, TaskOptions};
+use terminal_size::{terminal_size, Width};
+
+use crate::cli::{AddArgs, ListArgs, SuggestArgs};
+
+impl AddArgs {
+    /// Splits the arguments into the task text and its options, reading the
+    /// shorthand syntax when `--quick` is set. Explicit flags win over it.
+    pub fn into_task(self) -> (String, TaskOptions) {
+        let task = self.task.unwrap_or_else(|| {
+            let url = self.url.clone().unwrap_or_default();
+
+            page_title(&url).unwrap_or(url)
+        });
+        let options = TaskOptions {
+            points: self.points,
+            due: self.due,
+            wait_until: self.wait_until,
+            estimate: self.estimate,
+            priority: self.priority,
+            tags: self.tags,
+            parent: self.parent,
+            project: self.project,
+            assignee: self.assignee,
+            location: self.location,
+            url: self.url,
+            ephemeral: self.ephemeral,
+        };
+
+        if !self.quick {
+            return (task, options);
+        }
+
+        let draft = parse_quick(&task);
+        let mut tags = draft.options.tags;
+        tags.extend(options.tags);
+
+        (
+            draft.task,
+            TaskOptions {
+                due: options.due.or(draft.options.due),
+                priority: options.priority.or(draft.options.priority),
+                tags,
+                ..options
+            },
+        )
+    }
+}
+
+impl From<ListArgs> for ListOptions {
+    fn from(mut args: ListArgs) -> Self {
+        if args.done {
+            args.status.push(Status::Done);
+        }
+
+        if args.undone {
+            args.status
+                .extend([Status::Todo, Status::InProgress, Status::Waiting]);
+        }
+
+        Self {
+            all: args.all,
+            tree: args.tree,
+            waiting: args.waiting,
+            archived: args.archived,
+            links: args.links,
+            statuses: args.status,
+            tags: args.tags,
+            meta: args.meta,
+            project: args.project,
+            assignee: args.assignee,
+            location: args.location,
+            priority: args.priority,
+            due_before: args.due_before,
+            due_after: args.due_after,
+            fits: args.fits,
+            timestamps: args.timestamps,
+            sort: args.sort,
+            reverse: args.reverse,
+            checklist: args.checklist,
+            width: args.width.or_else(terminal_width),
+            summary: args.summary,
+        }
+    }
+}
+
+impl From<SuggestArgs> for ListOptions {
+    fn from(args: SuggestArgs) -> Self {
+        Self {
+            tags: args.tags,
+            location: args.location,
+            ..Self::default()
+        }
+    }
+}
+
+pub fn terminal_width() -> Option<usize> {
+    terminal_size().map(|(Width(width), _)| usize::from(width))
+}
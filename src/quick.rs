@@ -0,0 +1,147 @@
+use chrono::{Days, NaiveDate};
+
+use crate::{today, values::parse_day, Priority, TaskOptions};
+
+/// A task parsed from shorthand text, ready to be added.
+#[derive(Debug, Default, Clone)]
+pub struct Draft {
+    pub task: String,
+    pub options: TaskOptions,
+}
+
+/// Parses shorthand such as `call mom tomorrow !high #family` into a draft.
+///
+/// Recognized tokens are `#tag`, `!low`/`!medium`/`!high` (or `!`, `!!`,
+/// `!!!`), and due dates written as `YYYY-MM-DD`, `today`, `tomorrow`, a
+/// weekday name or `in N days`/`in N weeks`. Everything else is the task text.
+#[must_use]
+pub fn parse_quick(input: &str) -> Draft {
+    parse_quick_at(input, today())
+}
+
+fn parse_quick_at(input: &str, today: NaiveDate) -> Draft {
+    let tokens: Vec<&str> = input.split_whitespace().collect();
+    let mut draft = Draft::default();
+    let mut words = Vec::new();
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let token = tokens[index];
+        index += 1;
+
+        if let Some(tag) = token.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+            draft.options.tags.push(tag.to_string());
+        } else if let Some(priority) = parse_priority(token) {
+            draft.options.priority = Some(priority);
+        } else if let Some(due) = parse_day(token, today) {
+            draft.options.due = Some(due);
+        } else if let Some(due) = token
+            .eq_ignore_ascii_case("in")
+            .then(|| parse_offset(tokens.get(index..index + 2)?, today))
+            .flatten()
+        {
+            draft.options.due = Some(due);
+            index += 2;
+        } else {
+            words.push(token);
+        }
+    }
+
+    draft.task = words.join(" ");
+    draft
+}
+
+fn parse_priority(token: &str) -> Option<Priority> {
+    match token.to_lowercase().as_str() {
+        "!" | "!low" => Some(Priority::Low),
+        "!!" | "!medium" => Some(Priority::Medium),
+        "!!!" | "!high" => Some(Priority::High),
+        _ => None,
+    }
+}
+
+fn parse_offset(tokens: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    let count: u64 = tokens.first()?.parse().ok()?;
+    let days = match tokens.get(1)?.to_lowercase().as_str() {
+        "day" | "days" => count,
+        "week" | "weeks" => count * 7,
+        _ => return None,
+    };
+
+    today.checked_add_days(Days::new(days))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A Wednesday.
+    fn today() -> NaiveDate {
+        date(2024, 1, 3)
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("valid date")
+    }
+
+    #[test]
+    fn reads_tags_and_priority() {
+        let draft = parse_quick_at("call mom #family !high #phone", today());
+
+        assert_eq!(draft.task, "call mom");
+        assert_eq!(draft.options.tags, ["family", "phone"]);
+        assert_eq!(draft.options.priority, Some(Priority::High));
+        assert_eq!(draft.options.due, None);
+    }
+
+    #[test]
+    fn reads_priority_marks() {
+        for (mark, priority) in [
+            ("!", Priority::Low),
+            ("!!", Priority::Medium),
+            ("!!!", Priority::High),
+        ] {
+            let draft = parse_quick_at(&format!("water plants {mark}"), today());
+
+            assert_eq!(draft.options.priority, Some(priority));
+        }
+    }
+
+    #[test]
+    fn reads_due_dates() {
+        for (text, due) in [
+            ("pay rent today", today()),
+            ("pay rent tomorrow", date(2024, 1, 4)),
+            ("pay rent Friday", date(2024, 1, 5)),
+            ("pay rent wednesday", date(2024, 1, 10)),
+            ("pay rent 2024-02-01", date(2024, 2, 1)),
+            ("pay rent in 3 days", date(2024, 1, 6)),
+            ("pay rent in 2 weeks", date(2024, 1, 17)),
+        ] {
+            let draft = parse_quick_at(text, today());
+
+            assert_eq!(draft.task, "pay rent", "{text}");
+            assert_eq!(draft.options.due, Some(due), "{text}");
+        }
+    }
+
+    #[test]
+    fn keeps_weekday_abbreviations_as_text() {
+        for text in ["buy sun cream", "sat exam", "wed dress fitting"] {
+            let draft = parse_quick_at(text, today());
+
+            assert_eq!(draft.task, text);
+            assert_eq!(draft.options.due, None);
+        }
+    }
+
+    #[test]
+    fn keeps_unrelated_words_as_text() {
+        let draft = parse_quick_at("put in 3 boxes # !urgent", today());
+
+        assert_eq!(draft.task, "put in 3 boxes # !urgent");
+        assert!(draft.options.tags.is_empty());
+        assert_eq!(draft.options.priority, None);
+        assert_eq!(draft.options.due, None);
+    }
+}
@@ -0,0 +1,34 @@
+# taskrs shell integration for bash
+# Add `eval "$(taskrs shell-init bash)"` to your ~/.bashrc
+
+# Ctrl-X t prompts for a task and adds it
+__taskrs_quick_add() {
+    local task
+    read -r -e -p "New task: " task && [ -n "$task" ] && command taskrs add "$task"
+}
+bind -x '"\C-xt": __taskrs_quick_add'
+
+# Use the tasks.json of the current directory when there is one
+__taskrs_cd_hook() {
+    if [ -f "$PWD/tasks.json" ]; then
+        export TASKRS_PATH="$PWD"
+        __taskrs_local=1
+    elif [ -n "$__taskrs_local" ]; then
+        unset TASKRS_PATH __taskrs_local
+    fi
+}
+
+# Show the number of pending tasks and the focused task in the prompt
+__taskrs_prompt() {
+    local count focus
+    count=$(command taskrs count 2>/dev/null) && [ "$count" -gt 0 ] || return
+    focus=$(command taskrs focus 2>/dev/null)
+    if [ -n "$focus" ]; then
+        printf '[%s · %s] ' "$count" "$focus"
+    else
+        printf '[%s] ' "$count"
+    fi
+}
+
+PROMPT_COMMAND="__taskrs_cd_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+PS1='$(__taskrs_prompt)'"$PS1"
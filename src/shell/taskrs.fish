@@ -0,0 +1,35 @@
+# taskrs shell integration for fish
+# Add `taskrs shell-init fish | source` to your ~/.config/fish/config.fish
+
+# Ctrl-X t prompts for a task and adds it
+function __taskrs_quick_add
+    read -P "New task: " task; and test -n "$task"; and command taskrs add "$task"
+    commandline -f repaint
+end
+bind \cxt __taskrs_quick_add
+
+# Use the tasks.json of the current directory when there is one
+function __taskrs_cd_hook --on-variable PWD
+    if test -f "$PWD/tasks.json"
+        set -gx TASKRS_PATH "$PWD"
+        set -g __taskrs_local 1
+    else if set -q __taskrs_local
+        set -e TASKRS_PATH __taskrs_local
+    end
+end
+__taskrs_cd_hook
+
+# Show the number of pending tasks and the focused task in the prompt
+functions -c fish_prompt __taskrs_original_prompt
+function fish_prompt
+    set -l count (command taskrs count 2>/dev/null)
+    if test -n "$count"; and test "$count" -gt 0
+        set -l focus (command taskrs focus 2>/dev/null)
+        if test -n "$focus"
+            printf '[%s · %s] ' $count "$focus"
+        else
+            printf '[%s] ' $count
+        end
+    end
+    __taskrs_original_prompt
+end
@@ -0,0 +1,363 @@
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+use std::io;
+
+use crate::{
+    display_priority, display_status, display_tags, level, mark_blocked, today, validate_task,
+    DeleteMode, Status, TaskItem, TaskOptions, TaskRef, TaskStore,
+};
+
+const HELP: &str = "a add · x done · d delete · e edit · u undo · / filter · t all · q quit";
+
+impl TaskStore {
+    /// Runs the full-screen interface until the user quits.
+    pub fn tui(&self) {
+        let mut terminal = ratatui::init();
+        let result = App::new(self).run(&mut terminal);
+        ratatui::restore();
+
+        if let Err(error) = result {
+            eprintln!("Could not run the interface: {error}");
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    Filter,
+    Add,
+    Delete,
+}
+
+struct App<'a> {
+    store: &'a TaskStore,
+    tasks: Vec<TaskItem>,
+    /// Indexes in `tasks` of the rows shown, in order.
+    visible: Vec<usize>,
+    state: ListState,
+    mode: Mode,
+    filter: String,
+    input: String,
+    all: bool,
+    message: Option<String>,
+    /// Points of the done tasks, shown with the help when points are used.
+    score: Option<u32>,
+}
+
+impl<'a> App<'a> {
+    fn new(store: &'a TaskStore) -> Self {
+        let mut app = Self {
+            store,
+            tasks: Vec::new(),
+            visible: Vec::new(),
+            state: ListState::default(),
+            mode: Mode::Browse,
+            filter: String::new(),
+            input: String::new(),
+            all: false,
+            message: None,
+            score: None,
+        };
+
+        app.reload();
+        app
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                return Ok(());
+            }
+
+            self.message = None;
+
+            match self.mode {
+                Mode::Browse => {
+                    if !self.browse(key, terminal)? {
+                        return Ok(());
+                    }
+                }
+                Mode::Filter => self.edit_filter(key),
+                Mode::Add => self.add(key, terminal)?,
+                Mode::Delete => self.delete(key, terminal)?,
+            }
+        }
+    }
+
+    /// Handles a key of the task list, returning `false` to quit.
+    fn browse(&mut self, key: KeyEvent, terminal: &mut DefaultTerminal) -> io::Result<bool> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Down | KeyCode::Char('j') => self.state.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+            KeyCode::Home | KeyCode::Char('g') => self.state.select_first(),
+            KeyCode::End | KeyCode::Char('G') => self.state.select_last(),
+            KeyCode::Char('a') => {
+                self.input.clear();
+                self.mode = Mode::Add;
+            }
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char('t') => {
+                self.all = !self.all;
+                self.refresh();
+            }
+            KeyCode::Char('d') if self.selected().is_some() => self.mode = Mode::Delete,
+            KeyCode::Char('u') => self.undo(terminal)?,
+            KeyCode::Char('x' | ' ') => {
+                if let Some(task) = self.selected() {
+                    let status = if task.is_open() {
+                        Status::Done
+                    } else {
+                        Status::Todo
+                    };
+
+                    let id = task.id;
+
+                    self.store.mark_task(&TaskRef::Id(id), status, false);
+                    self.reload();
+                    terminal.clear()?;
+                    self.message = Some(format!(
+                        "{} task {id} — press u to undo",
+                        if status == Status::Done {
+                            "Done"
+                        } else {
+                            "Reopened"
+                        }
+                    ));
+                }
+            }
+            KeyCode::Char('e') => {
+                if let Some(task) = self.selected() {
+                    let id = TaskRef::Id(task.id);
+
+                    ratatui::restore();
+                    self.store.edit_task(&id);
+                    *terminal = ratatui::init();
+                    self.reload();
+                }
+            }
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    fn edit_filter(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char(character) => self.filter.push(character),
+            KeyCode::Backspace => {
+                self.filter.pop();
+            }
+            KeyCode::Enter => self.mode = Mode::Browse,
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.mode = Mode::Browse;
+            }
+            _ => {}
+        }
+
+        self.refresh();
+    }
+
+    fn add(&mut self, key: KeyEvent, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        match key.code {
+            KeyCode::Char(character) => self.input.push(character),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Enter => {
+                let task = self.input.trim().to_string();
+                self.mode = Mode::Browse;
+
+                if task.is_empty() {
+                    return Ok(());
+                }
+
+                // `add` would offer to keep invalid text as notes, which
+                // needs a prompt.
+                if let Err(error) = validate_task(&task, &self.store.config) {
+                    self.message = Some(error);
+                    return Ok(());
+                }
+
+                self.store.add_task(task, &TaskOptions::default());
+                self.reload();
+                self.state.select_last();
+                terminal.clear()?;
+            }
+            KeyCode::Esc => self.mode = Mode::Browse,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn delete(&mut self, key: KeyEvent, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        self.mode = Mode::Browse;
+
+        if key.code != KeyCode::Char('y') {
+            return Ok(());
+        }
+
+        if let Some(task) = self.selected() {
+            let id = task.id;
+
+            // Subtasks move to the parent of the deleted task.
+            self.store
+                .delete_task(&TaskRef::Id(id), Some(DeleteMode::Reassign));
+            self.reload();
+            terminal.clear()?;
+            self.message = Some(format!("Deleted task {id} — press u to undo"));
+        }
+
+        Ok(())
+    }
+
+    fn undo(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        // `undo` would say so on stderr, behind the interface.
+        if self.store.read_journal().undo.is_empty() {
+            self.message = Some("Nothing to undo".to_string());
+            return Ok(());
+        }
+
+        self.store.undo();
+        self.reload();
+        terminal.clear()?;
+        self.message = Some("Undid the last change".to_string());
+
+        Ok(())
+    }
+
+    fn reload(&mut self) {
+        self.tasks = self.store.load();
+        self.tasks.sort_by_key(|task| task.id);
+        mark_blocked(&mut self.tasks);
+        self.score = self.store.summary().and_then(|summary| summary.score);
+        self.refresh();
+    }
+
+    /// Recomputes the rows shown after a change of the tasks or the filter.
+    fn refresh(&mut self) {
+        let today = today();
+        let filter = self.filter.to_lowercase();
+
+        self.visible = self
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| (self.all || task.is_open()) && !task.is_waiting(today))
+            .filter(|(_, task)| {
+                task.task.to_lowercase().contains(&filter)
+                    || task.tags.iter().any(|tag| tag.contains(&filter))
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        let selected = match (self.state.selected(), self.visible.len()) {
+            (_, 0) => None,
+            (selected, count) => Some(selected.unwrap_or(0).min(count - 1)),
+        };
+
+        self.state.select(selected);
+    }
+
+    fn selected(&self) -> Option<&TaskItem> {
+        let index = self.visible.get(self.state.selected()?)?;
+
+        self.tasks.get(*index)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [list_area, status_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let today = today();
+
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|index| {
+                let task = &self.tasks[*index];
+                let mut line = format!(
+                    "{} {:>3} {:<3} {}",
+                    display_status(&task.status),
+                    task.id,
+                    display_priority(&task.priority),
+                    task.task
+                );
+
+                if !task.tags.is_empty() {
+                    line = format!("{line} {}", display_tags(&task.tags));
+                }
+
+                let style = if !task.is_open() {
+                    Style::new().add_modifier(Modifier::DIM)
+                } else if task.focused {
+                    Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else if task.is_overdue(today) {
+                    Style::new().fg(Color::Red)
+                } else if task.blocked {
+                    Style::new().fg(Color::DarkGray)
+                } else {
+                    Style::new()
+                };
+
+                ListItem::new(line).style(style)
+            })
+            .collect();
+
+        let title = if self.filter.is_empty() {
+            " taskrs ".to_string()
+        } else {
+            format!(" taskrs /{} ", self.filter)
+        };
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(list, list_area, &mut self.state);
+
+        let (status, input) = match self.mode {
+            Mode::Browse => (
+                self.message.clone().unwrap_or_else(|| {
+                    self.score.map_or_else(
+                        || HELP.to_string(),
+                        |score| format!("{HELP} · score {score} (level {})", level(score)),
+                    )
+                }),
+                false,
+            ),
+            Mode::Filter => (format!("/{}", self.filter), true),
+            Mode::Add => (format!("New task: {}", self.input), true),
+            Mode::Delete => (
+                format!(
+                    "Delete task {}? (y/N)",
+                    self.selected().map_or(0, |task| task.id)
+                ),
+                false,
+            ),
+        };
+
+        if input {
+            let column = u16::try_from(status.chars().count()).unwrap_or(u16::MAX);
+            frame.set_cursor_position((status_area.x.saturating_add(column), status_area.y));
+        }
+
+        frame.render_widget(Paragraph::new(status), status_area);
+    }
+}
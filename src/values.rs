@@ -0,0 +1,487 @@
+// Values read from the command line. build.rs includes this file to render
+// the man page, so it must only depend on external crates.
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveTime, TimeDelta, Utc, Weekday};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+const MIN_UUID_PREFIX_LENGTH: usize = 8;
+/// Time of day used when a moment is given as a date only.
+pub const DEFAULT_TIME: NaiveTime = NaiveTime::from_hms_opt(9, 0, 0).expect("valid time");
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// Where a task stands. Done and cancelled tasks are closed, the others are
+/// still pending.
+#[derive(
+    Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Waiting,
+    Done,
+    Cancelled,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Todo => "todo",
+            Self::InProgress => "in-progress",
+            Self::Waiting => "waiting",
+            Self::Done => "done",
+            Self::Cancelled => "cancelled",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// What happens to the subtasks and dependent tasks of a deleted task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DeleteMode {
+    /// Delete the subtasks too.
+    Cascade,
+    /// Make the subtasks top-level tasks.
+    Orphan,
+    /// Move the subtasks to the deleted task's parent.
+    Reassign,
+}
+
+/// Order of the tasks shown by `list`.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Id,
+    Text,
+    /// Earliest due date first, tasks without one last.
+    Due,
+    /// Highest priority first, tasks without one last.
+    Priority,
+    Created,
+    /// Order set with `move`, new tasks last.
+    Manual,
+}
+
+/// Span of time covered by a report, ending today.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Period {
+    /// The last 7 days.
+    Week,
+    /// The last month.
+    Month,
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Week => "week",
+            Self::Month => "month",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// A task given on the command line, either by its numeric id or by a prefix
+/// of its UUID. Ids change with `swap`, UUIDs never do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskRef {
+    Id(u32),
+    Uuid(String),
+}
+
+impl FromStr for TaskRef {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_lowercase();
+
+        // Short numbers are ids, longer ones are most likely a pasted UUID prefix.
+        if value.len() < MIN_UUID_PREFIX_LENGTH {
+            if let Ok(id) = value.parse() {
+                return Ok(Self::Id(id));
+            }
+        }
+
+        if value.len() >= MIN_UUID_PREFIX_LENGTH
+            && value.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+        {
+            return Ok(Self::Uuid(value));
+        }
+
+        Err(format!(
+            "`{value}` is neither a task id nor a UUID prefix (at least {MIN_UUID_PREFIX_LENGTH} characters)"
+        ))
+    }
+}
+
+impl fmt::Display for TaskRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{id}"),
+            Self::Uuid(prefix) => f.write_str(prefix),
+        }
+    }
+}
+
+/// Tasks targeted by a command that changes several of them at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selector {
+    /// One task, by id or UUID prefix.
+    Task(TaskRef),
+    /// Tasks with an id in this range, such as `1-5`, missing ids being
+    /// skipped.
+    Range(u32, u32),
+    /// Tasks whose text contains this, case insensitively.
+    Matching(String),
+}
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        // UUID prefixes contain dashes too, ranges are between two ids.
+        if let Some((start, end)) = value.split_once('-') {
+            if let (Ok(TaskRef::Id(start)), Ok(TaskRef::Id(end))) = (start.parse(), end.parse()) {
+                if start > end {
+                    return Err(format!("`{value}` is an empty range"));
+                }
+
+                return Ok(Self::Range(start, end));
+            }
+        }
+
+        value.parse().map(Self::Task)
+    }
+}
+
+/// Parses a date given on the command line: `YYYY-MM-DD`, `today`,
+/// `tomorrow` or a weekday name.
+///
+/// # Errors
+///
+/// Fails when the value is none of the above.
+pub fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    parse_day(value, Local::now().date_naive()).ok_or_else(|| {
+        format!("`{value}` is not a date (YYYY-MM-DD, today, tomorrow or a weekday)")
+    })
+}
+
+/// Parses an effort estimate such as `30m`, `2h` or `1h30m` into minutes.
+///
+/// # Errors
+///
+/// Fails when the value is not made of hours and minutes.
+pub fn parse_estimate(value: &str) -> Result<u32, String> {
+    let error = || format!("`{value}` is not a duration (e.g. 30m, 2h or 1h30m)");
+    let mut minutes: u32 = 0;
+    let mut number = String::new();
+
+    for c in value.trim().to_lowercase().chars() {
+        let unit = match c {
+            '0'..='9' => {
+                number.push(c);
+                continue;
+            }
+            'h' => 60,
+            'm' => 1,
+            _ => return Err(error()),
+        };
+        let amount: u32 = number.parse().map_err(|_| error())?;
+
+        minutes = amount
+            .checked_mul(unit)
+            .and_then(|amount| minutes.checked_add(amount))
+            .ok_or_else(error)?;
+        number.clear();
+    }
+
+    if !number.is_empty() || value.trim().is_empty() {
+        return Err(error());
+    }
+
+    Ok(minutes)
+}
+
+/// Parses a moment given on the command line.
+///
+/// It is either a delay such as `30m` or `1h30m`, a time such as `14:00` or
+/// `9am` (its next occurrence), or a date as accepted by [`parse_date`] optionally
+/// followed by a time, e.g. `"friday 9:30"`. Dates alone mean 9:00.
+///
+/// # Errors
+///
+/// Fails when the value is none of the above.
+pub fn parse_moment(value: &str) -> Result<DateTime<Utc>, String> {
+    parse_moment_at(value, Local::now()).ok_or_else(|| {
+        format!("`{value}` is not a time (e.g. 30m, 14:00, 9am, tomorrow or \"friday 9:30\")")
+    })
+}
+
+fn parse_moment_at(value: &str, now: DateTime<Local>) -> Option<DateTime<Utc>> {
+    if let Ok(minutes) = parse_estimate(value) {
+        return Some((now + TimeDelta::minutes(i64::from(minutes))).to_utc());
+    }
+
+    let today = now.date_naive();
+    let words: Vec<&str> = value.split_whitespace().collect();
+    let (date, time) = match words.as_slice() {
+        [day] => {
+            if let Some(date) = parse_day(day, today) {
+                (date, DEFAULT_TIME)
+            } else {
+                let time = parse_time(day)?;
+                let date = if time > now.time() {
+                    today
+                } else {
+                    today.checked_add_days(Days::new(1))?
+                };
+
+                (date, time)
+            }
+        }
+        [day, time] => (parse_day(day, today)?, parse_time(time)?),
+        _ => return None,
+    };
+
+    date.and_time(time)
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|moment| moment.to_utc())
+}
+
+/// Parses a time of day on 24 hours, or on 12 hours with am or pm.
+fn parse_time(token: &str) -> Option<NaiveTime> {
+    let token = token.to_lowercase();
+    let Some(clock) = token
+        .strip_suffix("am")
+        .or_else(|| token.strip_suffix("pm"))
+    else {
+        return NaiveTime::parse_from_str(&token, "%H:%M").ok();
+    };
+    // Hours alone are whole hours, e.g. 9am.
+    let minutes = if clock.contains(':') { "" } else { ":00" };
+
+    NaiveTime::parse_from_str(
+        &format!("{clock}{minutes}{}", &token[clock.len()..]),
+        "%I:%M%p",
+    )
+    .ok()
+}
+
+/// Parses a single-word date, weekdays meaning their next occurrence.
+///
+/// Weekdays must be spelled in full: abbreviations such as `sun` or `sat`
+/// are common words in task text.
+pub fn parse_day(token: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let token = token.to_lowercase();
+
+    match token.as_str() {
+        "today" => Some(today),
+        "tomorrow" => today.checked_add_days(Days::new(1)),
+        _ => WEEKDAYS
+            .iter()
+            .find(|(name, _)| *name == token)
+            .map_or_else(
+                || NaiveDate::parse_from_str(&token, "%Y-%m-%d").ok(),
+                |(_, weekday)| {
+                    let ahead = (weekday.num_days_from_monday() + 7
+                        - today.weekday().num_days_from_monday())
+                        % 7;
+
+                    today.checked_add_days(Days::new(u64::from(if ahead == 0 { 7 } else { ahead })))
+                },
+            ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A Wednesday.
+    fn today() -> NaiveDate {
+        date(2024, 1, 3)
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("valid date")
+    }
+
+    fn at(date: NaiveDate, hour: u32, minute: u32) -> DateTime<Local> {
+        date.and_hms_opt(hour, minute, 0)
+            .and_then(|moment| moment.and_local_timezone(Local).earliest())
+            .expect("valid local time")
+    }
+
+    #[test]
+    fn reads_short_numbers_as_ids() {
+        assert_eq!("7".parse(), Ok(TaskRef::Id(7)));
+        assert_eq!(" 42 ".parse(), Ok(TaskRef::Id(42)));
+        assert_eq!("1234567".parse(), Ok(TaskRef::Id(1_234_567)));
+    }
+
+    #[test]
+    fn reads_long_values_as_uuid_prefixes() {
+        assert_eq!(
+            "12345678".parse(),
+            Ok(TaskRef::Uuid("12345678".to_string()))
+        );
+        assert_eq!(
+            "ABCDEF12".parse(),
+            Ok(TaskRef::Uuid("abcdef12".to_string()))
+        );
+        assert_eq!(
+            "a1b2c3d4-e5f6".parse(),
+            Ok(TaskRef::Uuid("a1b2c3d4-e5f6".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_other_task_references() {
+        for value in ["abcdef1", "buy milk", "", "-1", "a1b2c3d4-xyz"] {
+            assert!(value.parse::<TaskRef>().is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn displays_task_references_as_given() {
+        for value in ["7", "a1b2c3d4-e5f6"] {
+            let reference: TaskRef = value.parse().expect("valid reference");
+
+            assert_eq!(reference.to_string(), value);
+        }
+    }
+
+    #[test]
+    fn reads_ranges_of_ids() {
+        assert_eq!("1-5".parse(), Ok(Selector::Range(1, 5)));
+        assert_eq!("3-3".parse(), Ok(Selector::Range(3, 3)));
+        assert!("5-1".parse::<Selector>().is_err());
+        assert!("1-x".parse::<Selector>().is_err());
+    }
+
+    #[test]
+    fn reads_single_tasks_as_selectors() {
+        assert_eq!("3".parse(), Ok(Selector::Task(TaskRef::Id(3))));
+        assert_eq!(
+            "a1b2c3d4-e5f6".parse(),
+            Ok(Selector::Task(TaskRef::Uuid("a1b2c3d4-e5f6".to_string())))
+        );
+    }
+
+    #[test]
+    fn reads_estimates() {
+        for (value, minutes) in [
+            ("30m", 30),
+            ("2h", 120),
+            ("1h30m", 90),
+            ("1H", 60),
+            (" 45m ", 45),
+            ("0m", 0),
+        ] {
+            assert_eq!(parse_estimate(value), Ok(minutes), "{value}");
+        }
+    }
+
+    #[test]
+    fn rejects_other_estimates() {
+        for value in ["", "30", "h", "1d", "1.5h", "99999999999h"] {
+            assert!(parse_estimate(value).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn reads_days() {
+        for (token, day) in [
+            ("today", today()),
+            ("Tomorrow", date(2024, 1, 4)),
+            ("friday", date(2024, 1, 5)),
+            ("monday", date(2024, 1, 8)),
+            ("wednesday", date(2024, 1, 10)),
+            ("2024-02-01", date(2024, 2, 1)),
+        ] {
+            assert_eq!(parse_day(token, today()), Some(day), "{token}");
+        }
+    }
+
+    #[test]
+    fn only_reads_full_weekday_names() {
+        for token in ["wed", "sun", "sat", "fri", "mon", "soon"] {
+            assert_eq!(parse_day(token, today()), None, "{token}");
+        }
+    }
+
+    #[test]
+    fn reads_moments() {
+        let now = at(today(), 10, 0);
+
+        for (value, moment) in [
+            ("30m", at(today(), 10, 30)),
+            ("14:00", at(today(), 14, 0)),
+            ("09:00", at(date(2024, 1, 4), 9, 0)),
+            ("tomorrow", at(date(2024, 1, 4), 9, 0)),
+            ("friday 9:30", at(date(2024, 1, 5), 9, 30)),
+            ("9am", at(date(2024, 1, 4), 9, 0)),
+            ("tomorrow 9AM", at(date(2024, 1, 4), 9, 0)),
+            ("friday 2:30pm", at(date(2024, 1, 5), 14, 30)),
+            ("12pm", at(today(), 12, 0)),
+            ("2024-02-01 18:15", at(date(2024, 2, 1), 18, 15)),
+        ] {
+            assert_eq!(
+                parse_moment_at(value, now),
+                Some(moment.to_utc()),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_other_moments() {
+        let now = at(today(), 10, 0);
+
+        for value in [
+            "fri 9:30",
+            "tomorrow at 9",
+            "25:00",
+            "13pm",
+            "9 am",
+            "later",
+        ] {
+            assert_eq!(parse_moment_at(value, now), None, "{value}");
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{self, Command, Output},
+};
+
+use serde_json::Value;
+
+/// A store in its own temporary directory, removed when dropped.
+struct Store(PathBuf);
+
+impl Store {
+    fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("taskrs-{name}-{}", process::id()));
+
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).expect("temporary directory created");
+
+        Self(path)
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_taskrs"))
+            .arg("--path")
+            .arg(&self.0)
+            .args(args)
+            .output()
+            .expect("taskrs runs")
+    }
+
+    fn file(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+
+    /// Temporary files left in the directory.
+    fn temporary_files(&self) -> Vec<PathBuf> {
+        fs::read_dir(&self.0)
+            .expect("directory exists")
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "tmp"))
+            .collect()
+    }
+
+    /// Texts of the tasks in `name`, in order.
+    fn tasks(&self, name: &str) -> Vec<String> {
+        let content = fs::read(self.file(name)).expect("store exists");
+        let tasks: Vec<Value> = serde_json::from_slice(&content).expect("store is valid JSON");
+
+        tasks
+            .iter()
+            .map(|task| task["task"].as_str().unwrap_or_default().to_string())
+            .collect()
+    }
+}
+
+impl Drop for Store {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn crash_between_write_and_rename_keeps_the_store() {
+    let store = Store::new("crash");
+
+    assert!(store.run(&["add", "first"]).status.success());
+    assert!(!store
+        .run(&["--simulate-crash", "add", "second"])
+        .status
+        .success());
+
+    assert_eq!(store.tasks("tasks.json"), ["first"]);
+    assert_eq!(store.temporary_files().len(), 1);
+
+    assert!(store.run(&["add", "third"]).status.success());
+    assert_eq!(store.tasks("tasks.json"), ["first", "third"]);
+}
+
+#[test]
+fn concurrent_writes_leave_a_valid_store() {
+    let store = Store::new("concurrent");
+
+    assert!(store.run(&["add", "first"]).status.success());
+
+    let children: Vec<_> = (0..8)
+        .map(|_| {
+            Command::new(env!("CARGO_BIN_EXE_taskrs"))
+                .arg("--path")
+                .arg(&store.0)
+                .args(["add", "next"])
+                .spawn()
+                .expect("taskrs runs")
+        })
+        .collect();
+
+    for mut child in children {
+        assert!(child.wait().expect("taskrs exits").success());
+    }
+
+    // Concurrent commands may lose each other's changes, never the store.
+    assert!(store.tasks("tasks.json").len() >= 2);
+    assert!(store.temporary_files().is_empty());
+}
+
+#[test]
+fn partial_temporary_file_is_ignored() {
+    let store = Store::new("partial");
+
+    assert!(store.run(&["add", "first"]).status.success());
+    fs::write(store.file("tasks.json.tmp"), r#"[{"id":1,"ta"#).expect("file written");
+
+    let list = store.run(&["list"]);
+    assert!(list.status.success());
+    assert!(String::from_utf8_lossy(&list.stdout).contains("first"));
+
+    assert!(store.run(&["add", "second"]).status.success());
+    assert_eq!(store.tasks("tasks.json"), ["first", "second"]);
+}
+
+#[test]
+fn doctor_removes_temporary_files() {
+    let store = Store::new("doctor");
+
+    assert!(store.run(&["add", "first"]).status.success());
+
+    for name in [
+        "tasks.json.tmp",
+        "tasks.json.0123abcd.tmp",
+        "tasks.archive.json.tmp",
+    ] {
+        fs::write(store.file(name), "[").expect("file written");
+    }
+
+    let doctor = store.run(&["doctor"]);
+    assert!(doctor.status.success());
+    assert!(String::from_utf8_lossy(&doctor.stdout).contains("interrupted write"));
+
+    assert!(store.temporary_files().is_empty());
+    assert_eq!(store.tasks("tasks.json"), ["first"]);
+}
+
+#[test]
+fn interrupted_archive_is_repaired() {
+    let store = Store::new("archive");
+
+    for args in [&["add", "first"][..], &["add", "second"], &["done", "1"]] {
+        assert!(store.run(args).status.success());
+    }
+
+    // What a crash after writing the archive, before writing the store,
+    // leaves behind.
+    let before = fs::read(store.file("tasks.json")).expect("store exists");
+    assert!(store.run(&["archive", "--done"]).status.success());
+    fs::write(store.file("tasks.json"), &before).expect("store restored");
+
+    assert!(store.run(&["archive", "--done"]).status.success());
+    assert_eq!(store.tasks("tasks.json"), ["second"]);
+    assert_eq!(store.tasks("tasks.archive.json"), ["first"]);
+
+    fs::write(store.file("tasks.json"), &before).expect("store restored");
+
+    let doctor = store.run(&["doctor"]);
+    assert!(doctor.status.success());
+    assert!(String::from_utf8_lossy(&doctor.stdout).contains("already archived"));
+    assert_eq!(store.tasks("tasks.json"), ["second"]);
+    assert_eq!(store.tasks("tasks.archive.json"), ["first"]);
+}
+
+#[test]
+fn empty_store_has_no_tasks() {
+    let store = Store::new("empty");
+
+    fs::write(store.file("tasks.json"), "").expect("file written");
+
+    let count = store.run(&["count"]);
+    assert!(count.stderr.is_empty());
+    assert_eq!(String::from_utf8_lossy(&count.stdout), "0\n");
+
+    assert!(store.run(&["add", "first"]).status.success());
+    assert_eq!(store.tasks("tasks.json"), ["first"]);
+}